@@ -1,4 +1,5 @@
-use std::{env, fs};
+use std::collections::{HashMap, VecDeque};
+use std::{env, fmt, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
     let default_input_filename = &String::from("input/01");
@@ -7,7 +8,20 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // The audit lists the digits found on every line and the value they make
+    for flag in flags {
+        let format = match flag.as_str() {
+            "--audit" | "--audit=table" => AuditFormat::Table,
+            "--audit=csv" => AuditFormat::Csv,
+            _ => panic!("unknown flag {}", flag),
+        };
+        print!("{}", audit(&input, &format));
+    }
+
     let result_part_1 = part_1(&input);
     println!("{:?}", result_part_1);
 
@@ -15,56 +29,351 @@ fn main() {
     println!("{:?}", result_part_2);
 }
 
-fn part_1(input: &str) -> u32 {
+const ENGLISH_DIGIT_WORDS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Digit,
+    Word,
+}
+
+impl TokenKind {
+    fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Digit => "digit",
+            TokenKind::Word => "word",
+        }
+    }
+}
+
+/// A digit found in a line: `line[start..end]` is either the digit itself or a word for it
+#[derive(Debug, PartialEq)]
+struct DigitMatch {
+    start: usize,
+    end: usize,
+    value: u32,
+    kind: TokenKind,
+}
+
+#[derive(Debug, PartialEq)]
+enum CalibrationError {
+    NoDigits { line_number: usize, line: String },
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalibrationError::NoDigits { line_number, line } => {
+                write!(f, "line {} has no digits: {:?}", line_number, line)
+            }
+        }
+    }
+}
+
+/// Aho-Corasick automaton, that finds all (overlapping) words in a single pass over a line
+struct Automaton {
+    /// Trie of all words, `transitions[node][byte]` is the next node
+    transitions: Vec<HashMap<u8, usize>>,
+    /// The node for the longest proper suffix of the current node that is also in the trie
+    fail: Vec<usize>,
+    /// The (length, value, kind) of every word that ends at a node, including those via `fail`
+    outputs: Vec<Vec<(usize, u32, TokenKind)>>,
+}
+
+impl Automaton {
+    fn new(words: &[(Vec<u8>, u32, TokenKind)]) -> Self {
+        let mut automaton = Self {
+            transitions: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![Vec::new()],
+        };
+
+        for (word, value, kind) in words {
+            let mut node = 0;
+            for byte in word {
+                node = match automaton.transitions[node].get(byte) {
+                    Some(next) => *next,
+                    None => {
+                        automaton.transitions.push(HashMap::new());
+                        automaton.fail.push(0);
+                        automaton.outputs.push(Vec::new());
+                        let next = automaton.transitions.len() - 1;
+                        automaton.transitions[node].insert(*byte, next);
+                        next
+                    }
+                };
+            }
+            automaton.outputs[node].push((word.len(), *value, *kind));
+        }
+
+        // Breadth first, so the fail node of a parent is always known before its children
+        let mut queue = automaton.transitions[0]
+            .values()
+            .copied()
+            .collect::<VecDeque<_>>();
+        while let Some(node) = queue.pop_front() {
+            for (byte, child) in automaton.transitions[node].clone() {
+                let mut fail = automaton.fail[node];
+                while fail != 0 && !automaton.transitions[fail].contains_key(&byte) {
+                    fail = automaton.fail[fail];
+                }
+                let child_fail = match automaton.transitions[fail].get(&byte) {
+                    Some(next) if *next != child => *next,
+                    _ => 0,
+                };
+
+                automaton.fail[child] = child_fail;
+                let inherited = automaton.outputs[child_fail].clone();
+                automaton.outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        automaton
+    }
+
+    fn step(&self, mut node: usize, byte: u8) -> usize {
+        while node != 0 && !self.transitions[node].contains_key(&byte) {
+            node = self.fail[node];
+        }
+        self.transitions[node].get(&byte).copied().unwrap_or(0)
+    }
+}
+
+/// The words that count as digits, next to the digits 0-9 themselves. Overlapping words such as
+/// "eightwo" both count, so the first digit is searched for from the start of the line and the
+/// last digit from the end, with an automaton of the reversed words.
+struct DigitVocabulary {
+    forward: Automaton,
+    backward: Automaton,
+    max_word_len: usize,
+}
+
+impl DigitVocabulary {
+    /// `words[n]` is the word for digit `n`, empty words are skipped
+    fn from_words(words: &[&str]) -> Self {
+        let digits = (0..10).map(|n| (n.to_string(), n, TokenKind::Digit));
+        let words = words
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| !word.is_empty())
+            .map(|(n, word)| (word.to_string(), n as u32, TokenKind::Word));
+        let all_words = digits
+            .chain(words)
+            .map(|(word, value, kind)| (word.into_bytes(), value, kind))
+            .collect::<Vec<_>>();
+
+        let reversed_words = all_words
+            .iter()
+            .map(|(word, value, kind)| (word.iter().rev().copied().collect(), *value, *kind))
+            .collect::<Vec<_>>();
+
+        Self {
+            forward: Automaton::new(&all_words),
+            backward: Automaton::new(&reversed_words),
+            max_word_len: all_words
+                .iter()
+                .map(|(word, _, _)| word.len())
+                .max()
+                .unwrap(),
+        }
+    }
+
+    /// Only the digits 0-9
+    fn digits() -> Self {
+        Self::from_words(&[])
+    }
+
+    /// The digits 0-9 and "one" to "nine"
+    fn english() -> Self {
+        let mut words = ENGLISH_DIGIT_WORDS;
+        words[0] = "";
+        Self::from_words(&words)
+    }
+
+    /// The digit or word that starts first in the line
+    fn first_digit(&self, line: &str) -> Option<DigitMatch> {
+        let mut first: Option<DigitMatch> = None;
+        let mut node = 0;
+
+        for (i, byte) in line.bytes().enumerate() {
+            // Matches are found where they end, so keep going until no match that ends
+            // from here on can start before the one we already have
+            if first
+                .as_ref()
+                .is_some_and(|f| i + 1 >= f.start + self.max_word_len)
+            {
+                break;
+            }
+
+            node = self.forward.step(node, byte);
+            for (len, value, kind) in self.forward.outputs[node].iter() {
+                let start = i + 1 - len;
+                if first.as_ref().is_none_or(|f| start < f.start) {
+                    first = Some(DigitMatch {
+                        start,
+                        end: i + 1,
+                        value: *value,
+                        kind: *kind,
+                    });
+                }
+            }
+        }
+
+        first
+    }
+
+    /// The digit or word that starts last in the line
+    fn last_digit(&self, line: &str) -> Option<DigitMatch> {
+        let mut node = 0;
+
+        // In the reversed line, the first match that ends is the one that starts last in the line
+        for (i, byte) in line.bytes().rev().enumerate() {
+            node = self.backward.step(node, byte);
+            if let Some((len, value, kind)) = self.backward.outputs[node].first() {
+                let start = line.len() - i - 1;
+                return Some(DigitMatch {
+                    start,
+                    end: start + len,
+                    value: *value,
+                    kind: *kind,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+fn calibration_value(line: &str, vocabulary: &DigitVocabulary) -> Option<u32> {
+    let first = vocabulary.first_digit(line)?;
+    let last = vocabulary.last_digit(line)?;
+    Some(first.value * 10 + last.value)
+}
+
+fn calibration_sum(input: &str, vocabulary: &DigitVocabulary) -> Result<u32, CalibrationError> {
     input
-        .split("\n")
-        .map(|line| {
-            line.chars()
-                .filter_map(|char| char.to_digit(10))
-                .collect::<Vec<u32>>()
+        .split('\n')
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            calibration_value(line, vocabulary).ok_or_else(|| CalibrationError::NoDigits {
+                line_number: i + 1,
+                line: line.to_string(),
+            })
         })
-        .map(|digits| digits.first().unwrap_or(&0) * 10 + digits.last().unwrap_or(&0))
         .sum()
 }
 
-fn get_all_line_digits(line: &str) -> Vec<u32> {
-    line.chars()
+enum AuditFormat {
+    Table,
+    Csv,
+}
+
+const AUDIT_COLUMNS: [&str; 10] = [
+    "part",
+    "line",
+    "text",
+    "first",
+    "first offset",
+    "first kind",
+    "last",
+    "last offset",
+    "last kind",
+    "value",
+];
+
+/// One row per non-empty line and part, with the first and last digit that were found in it.
+/// The digit columns are empty for lines without digits.
+fn audit_rows(input: &str, part: usize, vocabulary: &DigitVocabulary) -> Vec<Vec<String>> {
+    input
+        .split('\n')
         .enumerate()
-        .filter_map(|(i, char)| match char.to_digit(10) {
-            Some(n) => Some(n),
-            None => {
-                if line[i..].starts_with("one") {
-                    Some(1)
-                } else if line[i..].starts_with("two") {
-                    Some(2)
-                } else if line[i..].starts_with("three") {
-                    Some(3)
-                } else if line[i..].starts_with("four") {
-                    Some(4)
-                } else if line[i..].starts_with("five") {
-                    Some(5)
-                } else if line[i..].starts_with("six") {
-                    Some(6)
-                } else if line[i..].starts_with("seven") {
-                    Some(7)
-                } else if line[i..].starts_with("eight") {
-                    Some(8)
-                } else if line[i..].starts_with("nine") {
-                    Some(9)
-                } else {
-                    None
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let first = vocabulary.first_digit(line);
+            let last = vocabulary.last_digit(line);
+
+            let mut row = vec![part.to_string(), (i + 1).to_string(), line.to_string()];
+            for token in [&first, &last] {
+                match token {
+                    Some(m) => row.extend([
+                        line[m.start..m.end].to_string(),
+                        m.start.to_string(),
+                        m.kind.name().to_string(),
+                    ]),
+                    None => row.extend([String::new(), String::new(), String::new()]),
                 }
             }
+            row.push(match (first, last) {
+                (Some(first), Some(last)) => (first.value * 10 + last.value).to_string(),
+                _ => String::new(),
+            });
+
+            row
         })
-        .collect::<Vec<u32>>()
+        .collect()
+}
+
+fn audit(input: &str, format: &AuditFormat) -> String {
+    let header = AUDIT_COLUMNS
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>();
+    let rows = [header]
+        .into_iter()
+        .chain(audit_rows(input, 1, &DigitVocabulary::digits()))
+        .chain(audit_rows(input, 2, &DigitVocabulary::english()))
+        .collect::<Vec<_>>();
+
+    match format {
+        AuditFormat::Csv => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|field| {
+                        if field.contains([',', '"']) {
+                            format!("\"{}\"", field.replace('"', "\"\""))
+                        } else {
+                            field.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+                    + "\n"
+            })
+            .collect(),
+        AuditFormat::Table => {
+            let widths = (0..AUDIT_COLUMNS.len())
+                .map(|c| rows.iter().map(|row| row[c].len()).max().unwrap())
+                .collect::<Vec<_>>();
+
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(widths.iter())
+                        .map(|(field, width)| format!("{:<width$}", field, width = width))
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_string()
+                        + "\n"
+                })
+                .collect()
+        }
+    }
+}
+
+fn part_1(input: &str) -> u32 {
+    calibration_sum(input, &DigitVocabulary::digits())
+        .unwrap_or_else(|e| panic!("invalid input: {}", e))
 }
 
 fn part_2(input: &str) -> u32 {
-    input
-        .split("\n")
-        .map(|line| get_all_line_digits(line))
-        .map(|digits| digits.first().unwrap_or(&0) * 10 + digits.last().unwrap_or(&0))
-        .sum()
+    calibration_sum(input, &DigitVocabulary::english())
+        .unwrap_or_else(|e| panic!("invalid input: {}", e))
 }
 
 #[cfg(test)]
@@ -97,4 +406,90 @@ zoneight234
     fn test_part_2() {
         assert_eq!(part_2(PART_2_SAMPLE_DATA), 281);
     }
+
+    #[test]
+    fn test_vocabulary() {
+        let english = DigitVocabulary::english();
+        assert_eq!(calibration_value("xtwone3four", &english), Some(24));
+        assert_eq!(calibration_value("zero0", &english), Some(0));
+
+        let with_zero = DigitVocabulary::from_words(&ENGLISH_DIGIT_WORDS);
+        assert_eq!(calibration_value("zero1", &with_zero), Some(1));
+
+        let dutch = DigitVocabulary::from_words(&[
+            "nul", "een", "twee", "drie", "vier", "vijf", "zes", "zeven", "acht", "negen",
+        ]);
+        assert_eq!(calibration_value("zeventweeen", &dutch), Some(71));
+        assert_eq!(
+            calibration_sum("achtwee\nnulx5\nnegeen\n", &dutch),
+            Ok(82 + 5 + 11)
+        );
+    }
+
+    #[test]
+    fn test_first_and_last_digit() {
+        let english = DigitVocabulary::english();
+        assert_eq!(
+            english.first_digit("eightwo"),
+            Some(DigitMatch {
+                start: 0,
+                end: 5,
+                value: 8,
+                kind: TokenKind::Word
+            })
+        );
+        assert_eq!(
+            english.last_digit("eightwo"),
+            Some(DigitMatch {
+                start: 4,
+                end: 7,
+                value: 2,
+                kind: TokenKind::Word
+            })
+        );
+
+        // "seven" is found after "even" has already ended, but starts first
+        let nested = DigitVocabulary::from_words(&["", "seven", "even"]);
+        assert_eq!(nested.first_digit("xseven").map(|m| m.value), Some(1));
+        assert_eq!(nested.last_digit("sevenx").map(|m| m.value), Some(2));
+
+        // The kind comes from the vocabulary, not from what the word looks like
+        let numeric = DigitVocabulary::from_words(&["", "", "", "", "", "", "", "+7"]);
+        let first = numeric.first_digit("a+7").unwrap();
+        assert_eq!((first.start, first.kind), (1, TokenKind::Word));
+        let last = numeric.last_digit("a+7b").unwrap();
+        assert_eq!((last.start, last.kind), (2, TokenKind::Digit));
+    }
+
+    #[test]
+    fn test_no_digits() {
+        assert_eq!(
+            calibration_sum("1abc2\n\nnothing here\n", &DigitVocabulary::english()),
+            Err(CalibrationError::NoDigits {
+                line_number: 3,
+                line: "nothing here".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_audit() {
+        let input = "two1nine\nabc\n";
+        assert_eq!(
+            audit(input, &AuditFormat::Table),
+            r#"part  line  text      first  first offset  first kind  last  last offset  last kind  value
+1     1     two1nine  1      3             digit       1     3            digit      11
+1     2     abc
+2     1     two1nine  two    0             word        nine  4            word       29
+2     2     abc
+"#
+        );
+        assert_eq!(
+            audit("a,1\n", &AuditFormat::Csv),
+            r#"part,line,text,first,first offset,first kind,last,last offset,last kind,value
+1,1,"a,1",1,2,digit,1,2,digit,11
+2,1,"a,1",1,2,digit,1,2,digit,11
+"#
+        );
+    }
 }
@@ -1,4 +1,9 @@
+#[cfg(test)]
+use itertools::Itertools;
+#[cfg(test)]
+use num::{integer::binomial, BigInt, BigRational, Zero};
 use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
 use std::{env, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
@@ -8,34 +13,133 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
-    let result_part_1 = part_1(&input);
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // `--bag=red:12,green:13,blue:14` sets the cubes in the bag for part 1
+    let mut bag = default_bag();
+    for flag in flags {
+        match flag.strip_prefix("--bag=") {
+            Some(value) => bag = parse_bag(value),
+            None => panic!("unknown flag {}", flag),
+        }
+    }
+
+    let result_part_1 = part_1(&input, &bag);
     println!("{:?}", result_part_1);
 
     let result_part_2 = part_2(&input);
     println!("{:?}", result_part_2);
 }
 
+type Colour = String;
+
+/// A number of cubes per colour, colours that are missing have 0 cubes
+type CubeCounts = HashMap<Colour, u32>;
+
+fn default_bag() -> CubeCounts {
+    CubeCounts::from([
+        ("red".to_string(), 12),
+        ("green".to_string(), 13),
+        ("blue".to_string(), 14),
+    ])
+}
+
+/// Parses a bag like `red:12,green:13,blue:14`
+fn parse_bag(bag_str: &str) -> CubeCounts {
+    bag_str
+        .split(',')
+        .map(|part| {
+            let (colour, amount) = part
+                .split_once(':')
+                .expect("bag should be written as colour:amount,colour:amount");
+            let amount = amount
+                .trim()
+                .parse::<u32>()
+                .expect("amount should be a number");
+            (colour.trim().to_string(), amount)
+        })
+        .collect()
+}
+
+/// The cubes of each colour that are shown in one round
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Round {
+    cubes: CubeCounts,
+}
+
+impl Round {
+    fn count(&self, colour: &str) -> u32 {
+        self.cubes.get(colour).copied().unwrap_or(0)
+    }
+
+    fn fits_in(&self, bag: &CubeCounts) -> bool {
+        self.cubes
+            .iter()
+            .all(|(colour, amount)| *amount <= bag.get(colour).copied().unwrap_or(0))
+    }
+
+    #[cfg(test)]
+    fn total(&self) -> u32 {
+        self.cubes.values().sum()
+    }
+}
+
 #[derive(Debug)]
 struct Game {
     id: u32,
-    red: u32,
-    green: u32,
-    blue: u32,
+    rounds: Vec<Round>,
 }
 
-struct RGB {
-    r: u32,
-    g: u32,
-    b: u32,
+impl Game {
+    /// The fewest cubes of each colour the bag must have contained
+    fn max_per_colour(&self) -> Round {
+        let mut max = Round::default();
+        for (colour, amount) in self.rounds.iter().flat_map(|round| round.cubes.iter()) {
+            let max_amount = max.cubes.entry(colour.clone()).or_insert(0);
+            *max_amount = (*max_amount).max(*amount);
+        }
+        max
+    }
+
+    /// The product of the fewest cubes of each of the given colours, which is 0 when
+    /// one of them never showed up in this game
+    fn power(&self, colours: &BTreeSet<Colour>) -> u32 {
+        let max = self.max_per_colour();
+        colours.iter().map(|colour| max.count(colour)).product()
+    }
+
+    /// The index of the first round that shows more cubes than the bag holds
+    fn first_impossible_round(&self, bag: &CubeCounts) -> Option<usize> {
+        self.rounds.iter().position(|round| !round.fits_in(bag))
+    }
+
+    fn is_possible(&self, bag: &CubeCounts) -> bool {
+        self.first_impossible_round(bag).is_none()
+    }
+
+    #[cfg(test)]
+    fn total_cubes_drawn(&self) -> u32 {
+        self.rounds.iter().map(|round| round.total()).sum()
+    }
+}
+
+/// All colours that show up in any of the games
+fn all_colours(games: &[Game]) -> BTreeSet<Colour> {
+    games
+        .iter()
+        .flat_map(|game| game.rounds.iter())
+        .flat_map(|round| round.cubes.keys().cloned())
+        .collect()
 }
 
 fn parse_games(input: &str) -> Vec<Game> {
     let game_id_regex = Regex::new(r"Game (?<game_id>\d+): ").unwrap();
-    let cube_counts_regex = Regex::new(r"(?<amount>\d+) (?<colour>(red|green|blue))").unwrap();
+    let cube_counts_regex = Regex::new(r"(?<amount>\d+) (?<colour>\w+)").unwrap();
 
     input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .map(|line| {
             let caps = game_id_regex.captures(line).unwrap();
@@ -46,59 +150,140 @@ fn parse_games(input: &str) -> Vec<Game> {
                 .parse::<u32>()
                 .unwrap();
 
-            let mut rgb = RGB { r: 0, g: 0, b:0 };
-            line.split(";")
-                .map(|line| line.trim())
-                .flat_map(|line| {
-                    line.split(", ").filter(|l| !l.is_empty()).map(|part| {
-                        let caps = cube_counts_regex.captures(part).unwrap();
-                        let amount = caps
-                            .name("amount")
-                            .unwrap()
-                            .as_str()
-                            .parse::<u32>()
-                            .unwrap();
-
-                        let colour = caps.name("colour").unwrap().as_str();
-
-                        (colour, amount)
-                    })
+            let rounds = line[caps.get(0).unwrap().end()..]
+                .split(';')
+                .map(|round_str| {
+                    let mut round = Round::default();
+                    round_str
+                        .split(',')
+                        .map(|part| part.trim())
+                        .filter(|part| !part.is_empty())
+                        .for_each(|part| {
+                            let caps = cube_counts_regex.captures(part).unwrap();
+                            let amount = caps
+                                .name("amount")
+                                .unwrap()
+                                .as_str()
+                                .parse::<u32>()
+                                .unwrap();
+                            let colour = caps.name("colour").unwrap().as_str();
+
+                            *round.cubes.entry(colour.to_string()).or_insert(0) += amount;
+                        });
+                    round
                 })
-                .for_each(|(colour, amount)| {
-                    if colour == "red" && amount > rgb.r {
-                        rgb.r = amount
-                    };
-                    if colour == "green" && amount > rgb.g {
-                        rgb.g = amount
-                    };
-                    if colour == "blue" && amount > rgb.b {
-                        rgb.b = amount
-                    };
-                });
+                .collect();
 
             Game {
                 id: game_id,
-                red: rgb.r,
-                green: rgb.g,
-                blue: rgb.b,
+                rounds,
+            }
+        })
+        .collect()
+}
+
+/// The exact probability of drawing the cubes of this round from the bag, when drawing without
+/// replacement: the hypergeometric probability `prod(C(bag[colour], round[colour])) / C(N, k)`,
+/// with `N` the number of cubes in the bag and `k` the number of cubes drawn.
+#[cfg(test)]
+fn round_likelihood(round: &Round, bag: &CubeCounts) -> BigRational {
+    let in_bag = |colour: &str| BigInt::from(bag.get(colour).copied().unwrap_or(0));
+    let bag_total = BigInt::from(bag.values().sum::<u32>());
+    let drawn = BigInt::from(round.total());
+
+    if drawn > bag_total {
+        return BigRational::zero();
+    }
+
+    let ways_to_draw_round = round
+        .cubes
+        .iter()
+        .map(|(colour, amount)| {
+            let amount = BigInt::from(*amount);
+            if amount > in_bag(colour) {
+                BigInt::zero()
+            } else {
+                binomial(in_bag(colour), amount)
             }
         })
+        .product::<BigInt>();
+
+    BigRational::new(ways_to_draw_round, binomial(bag_total, drawn))
+}
+
+/// The probability of all rounds of the game, when the cubes are put back in the bag between rounds
+#[cfg(test)]
+fn likelihood(game: &Game, bag: &CubeCounts) -> BigRational {
+    game.rounds
+        .iter()
+        .map(|round| round_likelihood(round, bag))
+        .product()
+}
+
+/// All ways to divide `total` cubes over the colours, with at least `minimum[colour]` cubes of each
+#[cfg(test)]
+fn bag_compositions(colours: &[Colour], minimum: &Round, total: u32) -> Vec<CubeCounts> {
+    let Some((colour, other_colours)) = colours.split_first() else {
+        return if total == 0 {
+            vec![CubeCounts::new()]
+        } else {
+            Vec::new()
+        };
+    };
+
+    // The last colour gets all remaining cubes
+    let min_amount = minimum.count(colour);
+    let max_amount = if other_colours.is_empty() {
+        total
+    } else {
+        total.saturating_sub(other_colours.iter().map(|c| minimum.count(c)).sum())
+    };
+
+    (min_amount..=max_amount)
+        .flat_map(|amount| {
+            bag_compositions(other_colours, minimum, total - amount)
+                .into_iter()
+                .map(move |mut bag| {
+                    bag.insert(colour.clone(), amount);
+                    bag
+                })
+        })
         .collect()
 }
 
-fn part_1(input: &str) -> u32 {
+/// The bag of `total` cubes of the given colours that makes the game most likely, with its likelihood.
+/// When several bags are equally likely, the first one in order of the colours is returned.
+#[cfg(test)]
+fn most_likely_bag(
+    game: &Game,
+    colours: &BTreeSet<Colour>,
+    total: u32,
+) -> Option<(CubeCounts, BigRational)> {
+    let colours = colours.iter().cloned().collect_vec();
+    bag_compositions(&colours, &game.max_per_colour(), total)
+        .into_iter()
+        .map(|bag| {
+            let likelihood = likelihood(game, &bag);
+            (bag, likelihood)
+        })
+        .fold(None, |best, (bag, likelihood)| match best {
+            Some((_, ref best_likelihood)) if *best_likelihood >= likelihood => best,
+            _ => Some((bag, likelihood)),
+        })
+}
+
+fn part_1(input: &str, bag: &CubeCounts) -> u32 {
     parse_games(input)
         .iter()
-        .filter(|game| game.red <= 12 && game.green <= 13 && game.blue <= 14)
+        .filter(|game| game.is_possible(bag))
         .map(|game| game.id)
         .sum()
 }
 
 fn part_2(input: &str) -> u32 {
-    parse_games(input)
-        .iter()
-        .map(|game| game.red * game.green * game.blue)
-        .sum()
+    let games = parse_games(input);
+    let colours = all_colours(&games);
+    games.iter().map(|game| game.power(&colours)).sum()
 }
 
 #[cfg(test)]
@@ -115,11 +300,78 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(SAMPLE_DATA), 8);
+        assert_eq!(part_1(SAMPLE_DATA, &default_bag()), 8);
     }
 
     #[test]
     fn test_part_2() {
         assert_eq!(part_2(SAMPLE_DATA), 2286);
     }
+
+    #[test]
+    fn test_rounds() {
+        let games = parse_games(SAMPLE_DATA);
+        assert_eq!(games[0].rounds.len(), 3);
+        assert_eq!(games[0].rounds[0].count("red"), 4);
+        assert_eq!(games[0].rounds[0].count("green"), 0);
+        assert_eq!(games[0].rounds[1].count("blue"), 6);
+        assert_eq!(games[0].total_cubes_drawn(), 18);
+
+        let bag = default_bag();
+        assert_eq!(games[0].first_impossible_round(&bag), None);
+        assert_eq!(games[2].first_impossible_round(&bag), Some(0));
+        assert_eq!(games[3].first_impossible_round(&bag), Some(2));
+        assert_eq!(games[3].power(&all_colours(&games)), 14 * 3 * 15);
+    }
+
+    #[test]
+    fn test_other_colours_and_bags() {
+        let input = r#"
+Game 1: 3 blue, 2 yellow; 1 red
+Game 2: 5 yellow, 1 red, 1 green, 1 blue
+"#;
+        let games = parse_games(input);
+        assert_eq!(
+            all_colours(&games).into_iter().collect_vec(),
+            vec!["blue", "green", "red", "yellow"]
+        );
+
+        // Game 1 has no green cubes at all, so its power is 0
+        assert_eq!(part_2(input), 5);
+
+        assert_eq!(part_1(input, &default_bag()), 0);
+        assert_eq!(part_1(input, &parse_bag("blue:3,yellow:2,red:1")), 1);
+        assert_eq!(
+            part_1(input, &parse_bag("red:1, green:1, blue:3, yellow:5")),
+            1 + 2
+        );
+    }
+
+    #[test]
+    fn test_likelihood() {
+        let games = parse_games(SAMPLE_DATA);
+        assert_eq!(
+            likelihood(&games[0], &default_bag()),
+            BigRational::new(1375920.into(), 84124750189_u64.into())
+        );
+        assert_eq!(likelihood(&games[2], &default_bag()), BigRational::zero());
+    }
+
+    #[test]
+    fn test_most_likely_bag() {
+        let games = parse_games("Game 1: 1 red; 1 red, 1 blue\n");
+        let colours = all_colours(&games);
+
+        // 1 red and 1 blue is the only possible bag of 2, and then every draw of 1 is 50/50
+        let (bag, likelihood) = most_likely_bag(&games[0], &colours, 2).unwrap();
+        assert_eq!(bag, parse_bag("red:1,blue:1"));
+        assert_eq!(likelihood, BigRational::new(1.into(), 2.into()));
+
+        // 2 red and 1 blue: 2/3 for the first round, and 2/3 for drawing one of each
+        let (bag, likelihood) = most_likely_bag(&games[0], &colours, 3).unwrap();
+        assert_eq!(bag, parse_bag("red:2,blue:1"));
+        assert_eq!(likelihood, BigRational::new(4.into(), 9.into()));
+
+        assert_eq!(most_likely_bag(&games[0], &colours, 1), None);
+    }
 }
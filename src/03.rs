@@ -1,5 +1,7 @@
 use itertools::Itertools;
-use std::collections::HashMap;
+use num::BigUint;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::RangeInclusive;
 use std::{env, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
@@ -9,11 +11,26 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // `--symbols` lists the part numbers around every kind of symbol
+    for flag in flags {
+        match flag.as_str() {
+            "--symbols" => {
+                for (char, numbers) in parse_input(&input).numbers_by_symbol() {
+                    println!("{}: {}", char, numbers.iter().join(", "));
+                }
+            }
+            _ => panic!("unknown flag {}", flag),
+        }
+    }
+
     let result_part_1 = part_1(&input);
     println!("{:?}", result_part_1);
 
-    let result_part_2 = part_2(&input);
+    let result_part_2 = part_2(&input, &GearRule::puzzle());
     println!("{:?}", result_part_2);
 }
 
@@ -76,23 +93,19 @@ impl Symbol {
 
 #[derive(Debug)]
 struct Number {
-    number: u32,
+    /// Unique for every number in the schematic, in reading order
+    id: usize,
+    number: BigUint,
     pos: XY,
+    /// Number of digits
+    len: u32,
 }
 
 impl Number {
     fn positions(&self) -> Vec<XY> {
-        let mut positions: Vec<XY> = Vec::new();
-        positions.push(self.pos);
-        if self.number >= 10 {
-            let right = self.pos.right();
-            positions.push(right);
-            if self.number >= 100 {
-                positions.push(right.right());
-            }
-        }
-
-        positions
+        (0..self.len)
+            .map(|i| XY(self.pos.0 + i, self.pos.1))
+            .collect::<Vec<XY>>()
     }
 
     fn surrounding_positions(&self) -> Vec<XY> {
@@ -115,69 +128,175 @@ impl Schematic {
             .flat_map(|num| num.positions().into_iter().map(move |pos| (pos, num)))
             .collect::<HashMap<_, _>>()
     }
+
+    fn adjacent_numbers<'a>(
+        symbol: &Symbol,
+        numbers_all_positions: &HashMap<XY, &'a Number>,
+    ) -> Vec<&'a Number> {
+        symbol
+            .surrounding_positions()
+            .into_iter()
+            .filter_map(|pos| numbers_all_positions.get(&pos).copied())
+            // A number can touch the symbol at several of the surrounding positions
+            .unique_by(|n| n.id)
+            .collect::<Vec<&Number>>()
+    }
+
+    /// The sum of the ratios of all symbols that are gears according to the rule
+    fn gear_ratio_sum(&self, rule: &GearRule) -> BigUint {
+        let numbers_all_positions = self.numbers_all_positions();
+        self.symbols
+            .values()
+            .filter(|sym| rule.symbols.contains(&sym.char))
+            .filter_map(|sym| {
+                let adjacent_numbers = Self::adjacent_numbers(sym, &numbers_all_positions)
+                    .iter()
+                    .map(|n| n.number.clone())
+                    .collect::<Vec<BigUint>>();
+                rule.ratio(&adjacent_numbers)
+            })
+            .sum()
+    }
+
+    /// For every kind of symbol, the part numbers that touch at least one of those symbols,
+    /// in reading order
+    fn numbers_by_symbol(&self) -> BTreeMap<char, Vec<BigUint>> {
+        let numbers_all_positions = self.numbers_all_positions();
+        let mut touched: BTreeMap<char, BTreeSet<(u32, u32)>> = BTreeMap::new();
+
+        for sym in self.symbols.values() {
+            for num in Self::adjacent_numbers(sym, &numbers_all_positions) {
+                touched
+                    .entry(sym.char)
+                    .or_default()
+                    .insert((num.pos.1, num.pos.0));
+            }
+        }
+
+        touched
+            .into_iter()
+            .map(|(char, positions)| {
+                let numbers = positions
+                    .into_iter()
+                    .map(|(y, x)| self.numbers[&XY(x, y)].number.clone())
+                    .collect();
+                (char, numbers)
+            })
+            .collect()
+    }
+}
+
+/// How the numbers around a gear are combined into its ratio, the puzzle only uses the product
+#[derive(Debug, PartialEq)]
+enum Combine {
+    Product,
+    #[cfg(test)]
+    Sum,
+    #[cfg(test)]
+    Max,
+}
+
+/// Which symbols are gears: one of `symbols` with a number of adjacent part numbers within `adjacent`
+#[derive(Debug, PartialEq)]
+struct GearRule {
+    symbols: Vec<char>,
+    adjacent: RangeInclusive<usize>,
+    combine: Combine,
+}
+
+impl GearRule {
+    /// A `*` with exactly two adjacent numbers, the ratio is their product
+    fn puzzle() -> Self {
+        Self {
+            symbols: vec!['*'],
+            adjacent: 2..=2,
+            combine: Combine::Product,
+        }
+    }
+
+    /// The ratio of a gear with these adjacent numbers, or `None` if it is not a gear
+    fn ratio(&self, adjacent_numbers: &[BigUint]) -> Option<BigUint> {
+        if !self.adjacent.contains(&adjacent_numbers.len()) {
+            return None;
+        }
+
+        let numbers = adjacent_numbers.iter().cloned();
+        Some(match self.combine {
+            Combine::Product => numbers.product(),
+            #[cfg(test)]
+            Combine::Sum => numbers.sum(),
+            #[cfg(test)]
+            Combine::Max => numbers.max().unwrap_or_default(),
+        })
+    }
+}
+
+/// Splits a line into runs of digits, as (start, digits)
+fn digit_runs(line: &str) -> Vec<(usize, &str)> {
+    let bytes = line.as_bytes();
+    let mut runs = Vec::new();
+    let mut x = 0;
+
+    while x < bytes.len() {
+        if !bytes[x].is_ascii_digit() {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < bytes.len() && bytes[x].is_ascii_digit() {
+            x += 1;
+        }
+        runs.push((start, &line[start..x]));
+    }
+
+    runs
 }
 
 fn parse_input(input: &str) -> Schematic {
     let symbols = input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .enumerate()
         .flat_map(|(y, line)| {
-            return line
-                .chars()
+            line.chars()
                 .enumerate()
-                .filter(|(_, char)| !char.is_numeric() && &char.to_string() != ".")
+                .filter(|(_, char)| !char.is_ascii_digit() && *char != '.')
                 .map(move |(x, char)| {
                     let xy = XY(x.try_into().unwrap(), y.try_into().unwrap());
                     (xy, Symbol { char, pos: xy })
-                });
+                })
         })
         .collect::<HashMap<_, _>>();
 
     let numbers = input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .enumerate()
         .flat_map(|(y, line)| {
-            line.char_indices().filter_map(move |(x, char)| {
-                if char.is_numeric() {
-                    if x > 0 {
-                        let prev_char = line.as_bytes()[x - 1] as char;
-                        if prev_char.is_numeric() {
-                            return None;
-                        }
-                    }
-
-                    let mut number = char.to_string();
-                    let next_char = line.as_bytes()[x + 1] as char;
-                    if next_char.is_numeric() {
-                        number = number + &next_char.to_string();
-                        let next_next_char = line.as_bytes()[x + 2] as char;
-
-                        if next_next_char.is_numeric() {
-                            number = number + &next_next_char.to_string();
-                        }
-                    }
-
-                    let xy = XY(x.try_into().unwrap(), y.try_into().unwrap());
-                    return Some((
-                        xy,
-                        Number {
-                            number: number.parse::<u32>().unwrap(),
-                            pos: xy,
-                        },
-                    ));
-                }
-
-                return None;
-            })
+            digit_runs(line)
+                .into_iter()
+                .map(move |(x, digits)| (x, y, digits))
+        })
+        .enumerate()
+        .map(|(id, (x, y, digits))| {
+            let xy = XY(x.try_into().unwrap(), y.try_into().unwrap());
+            (
+                xy,
+                Number {
+                    id,
+                    number: digits.parse::<BigUint>().unwrap(),
+                    pos: xy,
+                    len: digits.len().try_into().unwrap(),
+                },
+            )
         })
         .collect::<HashMap<_, _>>();
 
-    return Schematic { symbols, numbers };
+    Schematic { symbols, numbers }
 }
 
-fn part_1(input: &str) -> u32 {
+fn part_1(input: &str) -> BigUint {
     let schematic = parse_input(input);
 
     schematic
@@ -190,7 +309,7 @@ fn part_1(input: &str) -> u32 {
                 .any(|pos| schematic.symbols.contains_key(&pos));
 
             if has_adjacent_symbol {
-                Some(num.number)
+                Some(num.number.clone())
             } else {
                 None
             }
@@ -198,36 +317,26 @@ fn part_1(input: &str) -> u32 {
         .sum()
 }
 
-fn part_2(input: &str) -> u32 {
-    let schematic = parse_input(input);
-
-    let numbers_all_positions = schematic.numbers_all_positions();
-    schematic
-        .symbols
-        .values()
-        .filter(|sym| sym.char == '*')
-        .filter_map(|sym| {
-            let adjacent_numbers = sym
-                .surrounding_positions()
-                .into_iter()
-                .filter_map(|pos| numbers_all_positions.get(&pos))
-                .dedup_by(|a, b| a.pos == b.pos && a.number == b.number)
-                .map(|n| n.number)
-                .collect::<Vec<u32>>();
-
-            if adjacent_numbers.len() == 2 {
-                let product = adjacent_numbers.iter().product::<u32>();
-                return Some(product);
-            }
-
-            return None;
-        })
-        .sum()
+fn part_2(input: &str, gear_rule: &GearRule) -> BigUint {
+    parse_input(input).gear_ratio_sum(gear_rule)
 }
 
 #[cfg(test)]
 mod tests_03 {
     use super::*;
+    use std::collections::HashSet;
+
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn rule(symbols: &str, adjacent: RangeInclusive<usize>, combine: Combine) -> GearRule {
+        GearRule {
+            symbols: symbols.chars().collect(),
+            adjacent,
+            combine,
+        }
+    }
 
     const SAMPLE_DATA: &str = r#"
 467..114..
@@ -244,11 +353,203 @@ mod tests_03 {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(SAMPLE_DATA), 4361);
+        assert_eq!(part_1(SAMPLE_DATA), big(4361));
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2(SAMPLE_DATA), 467835);
+        assert_eq!(part_2(SAMPLE_DATA, &GearRule::puzzle()), big(467835));
+    }
+
+    #[test]
+    fn test_numbers_of_any_length() {
+        let schematic = parse_input(
+            r#"
+1234*
+.....
+5...7
+#..89
+12345678901234567890123*
+"#,
+        );
+        let mut numbers = schematic
+            .numbers
+            .values()
+            .map(|n| (n.number.to_string(), n.pos, n.len))
+            .collect_vec();
+        numbers.sort_by_key(|(_, pos, _)| (pos.1, pos.0));
+        assert_eq!(
+            numbers,
+            vec![
+                ("1234".to_string(), XY(0, 0), 4),
+                ("5".to_string(), XY(0, 2), 1),
+                ("7".to_string(), XY(4, 2), 1),
+                ("89".to_string(), XY(3, 3), 2),
+                ("12345678901234567890123".to_string(), XY(0, 4), 23),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_edges() {
+        // Numbers at the start and end of lines, and in the first and last row
+        let input = r#"
+12*...3456
+.........*
+*78.....90
+"#;
+        assert_eq!(part_1(input), big(12 + 3456 + 78 + 90));
+        assert_eq!(part_2(input, &GearRule::puzzle()), big(3456 * 90));
+        assert_eq!(digit_runs("7..12345"), vec![(0, "7"), (3, "12345")]);
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        // The `*` next to 467 and 35 and the one next to 755 and 598
+        let gear_rule = rule("*", 2..=2, Combine::Sum);
+        assert_eq!(part_2(SAMPLE_DATA, &gear_rule), big(467 + 35 + 755 + 598));
+
+        // Also the `*` next to only 617
+        let gear_rule = rule("*", 1..=2, Combine::Max);
+        assert_eq!(part_2(SAMPLE_DATA, &gear_rule), big(467 + 617 + 755));
+
+        let gear_rule = rule("#$+", 1..=1, Combine::Product);
+        assert_eq!(part_2(SAMPLE_DATA, &gear_rule), big(633 + 664 + 592));
+    }
+
+    #[test]
+    fn test_numbers_by_symbol() {
+        let big_vec = |numbers: &[u64]| numbers.iter().map(|&n| big(n)).collect_vec();
+        assert_eq!(
+            parse_input(SAMPLE_DATA).numbers_by_symbol(),
+            BTreeMap::from([
+                ('#', big_vec(&[633])),
+                ('$', big_vec(&[664])),
+                ('*', big_vec(&[467, 35, 617, 755, 598])),
+                ('+', big_vec(&[592])),
+            ])
+        );
+    }
+
+    /// Xorshift, to generate the same random schematics on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, max: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % max
+        }
+    }
+
+    fn random_schematic(rng: &mut Rng) -> String {
+        let width = 1 + rng.next(12) as usize;
+        let height = 1 + rng.next(8) as usize;
+        (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| match rng.next(10) {
+                        0..=3 => '.',
+                        4..=7 => char::from_digit(rng.next(10) as u32, 10).unwrap(),
+                        8 => '*',
+                        _ => ['#', '$', '+', '/'][rng.next(4) as usize],
+                    })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+
+    /// Straight from the grid: (sum of part numbers, sum of gear ratios)
+    fn brute_force(input: &str) -> (u64, u64) {
+        let grid = input
+            .split('\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| l.as_bytes())
+            .collect_vec();
+        let cell = |x: i64, y: i64| -> u8 {
+            if x < 0 || y < 0 || y as usize >= grid.len() || x as usize >= grid[y as usize].len() {
+                b'.'
+            } else {
+                grid[y as usize][x as usize]
+            }
+        };
+        let neighbours = |x: i64, y: i64| {
+            (-1..=1)
+                .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                .filter(move |pos| *pos != (x, y))
+        };
+        // The start of the number a digit belongs to
+        let number_start = |mut x: i64, y: i64| {
+            while cell(x - 1, y).is_ascii_digit() {
+                x -= 1;
+            }
+            (x, y)
+        };
+        let number_at = |mut x: i64, y: i64| {
+            let mut number = 0;
+            while cell(x, y).is_ascii_digit() {
+                number = number * 10 + (cell(x, y) - b'0') as u64;
+                x += 1;
+            }
+            number
+        };
+
+        let mut part_numbers = HashSet::new();
+        let mut gear_ratios = 0;
+        for (y, row) in grid.iter().enumerate() {
+            for (x, char) in row.iter().enumerate() {
+                if char.is_ascii_digit() || *char == b'.' {
+                    continue;
+                }
+                let adjacent = neighbours(x as i64, y as i64)
+                    .filter(|(nx, ny)| cell(*nx, *ny).is_ascii_digit())
+                    .map(|(nx, ny)| number_start(nx, ny))
+                    .collect::<HashSet<_>>();
+                if *char == b'*' && adjacent.len() == 2 {
+                    gear_ratios += adjacent
+                        .iter()
+                        .map(|(x, y)| number_at(*x, *y))
+                        .product::<u64>();
+                }
+                part_numbers.extend(adjacent);
+            }
+        }
+
+        let part_number_sum = part_numbers.iter().map(|(x, y)| number_at(*x, *y)).sum();
+        (part_number_sum, gear_ratios)
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let mut rng = Rng(0x2023_1203);
+        for _ in 0..500 {
+            let input = random_schematic(&mut rng);
+            let (part_number_sum, gear_ratios) = brute_force(&input);
+            assert_eq!(
+                (part_1(&input), part_2(&input, &GearRule::puzzle())),
+                (big(part_number_sum), big(gear_ratios)),
+                "schematic:\n{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_touching_gear_twice() {
+        // 12 touches the gear at its top left and top, 345 at its bottom right, bottom and
+        // bottom left. Those positions follow each other in `surround()`, the brute force
+        // test above covers the other shapes.
+        let input = r#"
+12.
+.*.
+345
+"#;
+        assert_eq!(part_2(input, &GearRule::puzzle()), big(12 * 345));
+        assert_eq!(
+            part_2(input, &rule("*", 2..=2, Combine::Sum)),
+            big(12 + 345)
+        );
     }
 }
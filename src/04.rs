@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::{env, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
@@ -9,7 +9,20 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // Per card: matches, points and how many copies it holds and wins
+    for flag in flags {
+        let format = match flag.as_str() {
+            "--stats" | "--stats=table" => StatsFormat::Table,
+            "--stats=json" => StatsFormat::Json,
+            _ => panic!("unknown flag {}", flag),
+        };
+        print!("{}", format_stats(&card_stats(&input), &format));
+    }
+
     let result_part_1 = part_1(&input);
     println!("{:?}", result_part_1);
 
@@ -17,99 +30,185 @@ fn main() {
     println!("{:?}", result_part_2);
 }
 
-fn part_1(input: &str) -> u32 {
-    input
-        .split("\n")
-        .filter(|l| !l.is_empty())
-        .map(|l| l.split(":").nth(1).unwrap())
-        .map(|l| {
-            l.split("|")
-                .map(|p| {
-                    p.trim()
-                        .split(" ")
-                        .flat_map(|s| s.parse::<u32>())
-                        .collect::<HashSet<u32>>()
-                })
-                .collect::<Vec<_>>()
-                .chunks(2)
-                .map(|c| {
-                    let intersect = &c[0].intersection(&c[1]).collect::<Vec<_>>();
-                    let num_wins = intersect.len();
-
-                    match num_wins {
-                        0 => 0,
-                        _ => 2_u32.pow((num_wins as u32) - 1),
-                    }
-                })
-                .exactly_one()
-                .unwrap()
-        })
-        .sum()
-}
-
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 struct Card {
-    number: u32,
-    winning: HashSet<u32>,
-    ours: HashSet<u32>,
+    id: usize,
+    /// The number of our numbers (after the `|`) that are also winning numbers (before the `|`)
+    matches: usize,
 }
 
 impl Card {
-    fn new(number: u32, winning: &HashSet<u32>, ours: &HashSet<u32>) -> Card {
-        Card {
-            number,
-            winning: winning.clone(),
-            ours: ours.clone(),
+    fn points(&self) -> u64 {
+        match self.matches {
+            0 => 0,
+            _ => 1_u64
+                .checked_shl(self.matches as u32 - 1)
+                .expect("points should fit in a u64"),
         }
     }
+}
 
-    fn score(&self) -> u32 {
-        self.winning
-            .intersection(&self.ours)
-            .collect::<Vec<_>>()
-            .len() as u32
-    }
+fn parse_card(line: &str) -> Card {
+    let (card_str, numbers_str) = line.split_once(':').unwrap();
+    let id = card_str
+        .strip_prefix("Card")
+        .and_then(|id| id.trim().parse::<usize>().ok())
+        .unwrap_or_else(|| panic!("card should start with its id: {}", line));
+    let (winning_str, ours_str) = numbers_str.split_once('|').unwrap();
+
+    let winning = winning_str
+        .split_whitespace()
+        .map(|s| s.parse::<u32>().unwrap())
+        .collect::<HashSet<u32>>();
+
+    // A number we have more than once only matches once
+    let matches = ours_str
+        .split_whitespace()
+        .map(|s| s.parse::<u32>().unwrap())
+        .collect::<HashSet<u32>>()
+        .intersection(&winning)
+        .count();
+
+    Card { id, matches }
 }
 
-fn part_2(input: &str) -> u32 {
-    let cards = input
-        .split("\n")
+/// Parses the cards one by one, they must be numbered 1, 2, 3, ...
+fn parse_cards(input: &str) -> impl Iterator<Item = Card> + '_ {
+    input
+        .split('\n')
         .filter(|l| !l.is_empty())
+        .map(parse_card)
         .enumerate()
-        .map(|(i, l)| {
-            let numbers_str = l.split(":").nth(1).unwrap();
-            let numbers_vecs = numbers_str
-                .split("|")
-                .map(|p| {
-                    p.trim()
-                        .split(" ")
-                        .flat_map(|s| s.parse::<u32>())
-                        .collect::<HashSet<u32>>()
-                })
-                .collect::<Vec<_>>();
-
-            return Card::new(
-                i as u32 + 1,
-                &numbers_vecs[0],
-                &numbers_vecs[1],
-            );
+        .map(|(i, card)| {
+            if card.id != i + 1 {
+                panic!("expected card {}, found card {}", i + 1, card.id);
+            }
+            card
         })
-        .collect::<Vec<Card>>();
+}
 
-    let num_cards: usize = cards.len();
-    let mut multipliers = vec![1; num_cards];
+/// Every card with the number of copies of it we end up with, in a single pass
+fn cards_with_copies(input: &str) -> impl Iterator<Item = (Card, u64)> + '_ {
+    // The extra copies won so far for the upcoming cards, the front is the next card
+    let mut extra_copies: VecDeque<u64> = VecDeque::new();
 
-    for card in cards {
-        let card_idx = card.number - 1;
-        let this_card_mp = multipliers[card_idx as usize];
+    parse_cards(input).map(move |card| {
+        let copies = 1 + extra_copies.pop_front().unwrap_or(0);
 
-        for win in 1..=(card.score()) {
-            let card_to_up_num = card_idx + win;
-            multipliers[card_to_up_num as usize] += this_card_mp;
+        if extra_copies.len() < card.matches {
+            extra_copies.resize(card.matches, 0);
         }
-    }
+        for extra in extra_copies.iter_mut().take(card.matches) {
+            *extra = extra
+                .checked_add(copies)
+                .expect("number of cards should fit in a u64");
+        }
+
+        (card, copies)
+    })
+}
+
+fn part_1(input: &str) -> u64 {
+    parse_cards(input).map(|card| card.points()).sum()
+}
+
+fn part_2(input: &str) -> u64 {
+    // Copies won for cards past the end of the table are never counted
+    cards_with_copies(input).fold(0, |total, (_, copies)| {
+        total
+            .checked_add(copies)
+            .expect("number of cards should fit in a u64")
+    })
+}
+
+#[derive(Debug, PartialEq)]
+struct CardStats {
+    id: usize,
+    matches: usize,
+    points: u64,
+    /// The number of copies of later cards that all copies of this card win together
+    copies_won: u64,
+    /// The number of copies of this card, including the original
+    copies_held: u64,
+}
 
-    multipliers.into_iter().sum::<u32>()
+enum StatsFormat {
+    Table,
+    Json,
+}
+
+fn card_stats(input: &str) -> Vec<CardStats> {
+    let cards = cards_with_copies(input).collect_vec();
+    let num_cards = cards.len();
+
+    cards
+        .into_iter()
+        .map(|(card, copies)| {
+            let cards_after = num_cards - card.id;
+            CardStats {
+                id: card.id,
+                matches: card.matches,
+                points: card.points(),
+                copies_won: copies
+                    .checked_mul(card.matches.min(cards_after) as u64)
+                    .expect("number of cards should fit in a u64"),
+                copies_held: copies,
+            }
+        })
+        .collect()
+}
+
+fn format_stats(stats: &[CardStats], format: &StatsFormat) -> String {
+    match format {
+        StatsFormat::Json => {
+            let cards = stats
+                .iter()
+                .map(|card| {
+                    format!(
+                        r#"  {{"card": {}, "matches": {}, "points": {}, "copies_won": {}, "copies_held": {}}}"#,
+                        card.id, card.matches, card.points, card.copies_won, card.copies_held
+                    )
+                })
+                .join(",\n");
+            format!("[\n{}\n]\n", cards)
+        }
+        StatsFormat::Table => {
+            let header = ["card", "matches", "points", "copies won", "copies held"];
+            let rows = stats
+                .iter()
+                .map(|card| {
+                    [
+                        card.id.to_string(),
+                        card.matches.to_string(),
+                        card.points.to_string(),
+                        card.copies_won.to_string(),
+                        card.copies_held.to_string(),
+                    ]
+                })
+                .collect_vec();
+            let widths = (0..header.len())
+                .map(|c| {
+                    rows.iter()
+                        .map(|row| row[c].len())
+                        .chain([header[c].len()])
+                        .max()
+                        .unwrap()
+                })
+                .collect_vec();
+
+            [header.map(|h| h.to_string())]
+                .iter()
+                .chain(rows.iter())
+                .map(|row| {
+                    row.iter()
+                        .zip(widths.iter())
+                        .map(|(field, width)| format!("{:>width$}", field, width = width))
+                        .join("  ")
+                        + "\n"
+                })
+                .collect()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -134,4 +233,69 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
     fn test_part_2() {
         assert_eq!(part_2(SAMPLE_DATA), 30);
     }
+
+    #[test]
+    fn test_card_stats() {
+        let stats = card_stats(SAMPLE_DATA);
+        assert_eq!(
+            stats[1],
+            CardStats {
+                id: 2,
+                matches: 2,
+                points: 2,
+                copies_won: 4,
+                copies_held: 2
+            }
+        );
+
+        // Card 5 has no matches, card 6 has no cards after it
+        let stats = card_stats(&SAMPLE_DATA.replace("31 18 13 56 72", "74 77 13 56 72"));
+        assert_eq!(
+            format_stats(&stats, &StatsFormat::Table),
+            r#"card  matches  points  copies won  copies held
+   1        4       8           4            1
+   2        2       2           4            2
+   3        2       2           8            4
+   4        1       1           8            8
+   5        0       0           0           14
+   6        2       2           0            1
+"#
+        );
+        assert_eq!(
+            format_stats(&stats[..2], &StatsFormat::Json),
+            r#"[
+  {"card": 1, "matches": 4, "points": 8, "copies_won": 4, "copies_held": 1},
+  {"card": 2, "matches": 2, "points": 2, "copies_won": 4, "copies_held": 2}
+]
+"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected card 2, found card 3")]
+    fn test_card_ids_are_consecutive() {
+        part_1("Card 1: 1 | 1\nCard 3: 2 | 2\n");
+    }
+
+    #[test]
+    fn test_repeated_numbers() {
+        assert_eq!(parse_card("Card 1: 1 2 | 1 1").matches, 1);
+        assert_eq!(part_1("Card 1: 1 2 | 1 1\n"), 1);
+        assert_eq!(part_2("Card 1: 1 2 | 1 1\nCard 2: 3 | 4\n"), 3);
+    }
+
+    #[test]
+    fn test_wins_past_last_card() {
+        assert_eq!(part_2("Card 1: 1 2 | 1 2\n"), 1);
+        assert_eq!(part_2("Card 1: 1 2 | 1 2\nCard 2: 3 | 3\n"), 1 + 2);
+    }
+
+    #[test]
+    fn test_many_copies() {
+        // Every card wins a copy of the next two cards, the copies grow like the Fibonacci numbers
+        let input = (1..=70)
+            .map(|i| format!("Card {}: 1 2 3 | 1 2 4\n", i))
+            .collect::<String>();
+        assert_eq!(part_2(&input), 1304969544928584);
+    }
 }
@@ -1,6 +1,6 @@
 use num::integer::lcm;
 use std::collections::HashMap;
-use std::{env, fs};
+use std::{env, fmt, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
     let default_input_filename = &String::from("input/08");
@@ -24,45 +24,209 @@ struct Node {
     right: String,
 }
 
-fn parse_nodes(input: &str) -> HashMap<String, Node> {
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Recursive-descent parser for a single `NAME = (LEFT, RIGHT)` line.
+/// Whitespace between tokens is optional, names can be of any length.
+struct LineParser<'a> {
+    line: &'a str,
+    line_number: usize,
+    pos: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(line: &'a str, line_number: usize) -> Self {
+        Self {
+            line,
+            line_number,
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line_number,
+            column: self.pos + 1,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.line[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(format!("expected '{}', found end of line", expected))),
+        }
+    }
+
+    /// Returns the identifier and the (1-based) column it starts at
+    fn identifier(&mut self) -> Result<(&'a str, usize), ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            self.pos += c.len_utf8();
+        }
+
+        if self.pos == start {
+            return Err(match self.peek() {
+                Some(c) => self.error(format!("expected node name, found '{}'", c)),
+                None => self.error("expected node name, found end of line".to_string()),
+            });
+        }
+
+        Ok((&self.line[start..self.pos], start + 1))
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) => Err(self.error(format!("unexpected '{}' after node definition", c))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The lines that aren't blank, with their (1-based) line number. The first one has the
+/// instructions, the others the nodes.
+fn non_blank_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
     input
-        .split("\n")
-        .filter(|l| !l.is_empty())
-        .skip(1)
-        .map(|l| {
-            let name = (&l[0..3]).to_string();
-            let left = &l[7..10];
-            let right = &l[12..15];
-
-            let node = Node {
-                name: name.clone(),
+        .split('\n')
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| (i + 1, l))
+}
+
+fn parse_nodes(input: &str) -> Result<HashMap<String, Node>, ParseError> {
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    let mut references: Vec<(String, usize, usize)> = Vec::new();
+
+    for (line_number, line) in non_blank_lines(input).skip(1) {
+        let mut parser = LineParser::new(line, line_number);
+
+        let (name, name_column) = parser.identifier()?;
+        parser.expect('=')?;
+        parser.expect('(')?;
+        let (left, left_column) = parser.identifier()?;
+        parser.expect(',')?;
+        let (right, right_column) = parser.identifier()?;
+        parser.expect(')')?;
+        parser.expect_end()?;
+
+        if nodes.contains_key(name) {
+            return Err(ParseError {
+                line: line_number,
+                column: name_column,
+                message: format!("node '{}' is defined more than once", name),
+            });
+        }
+
+        references.push((left.to_string(), line_number, left_column));
+        references.push((right.to_string(), line_number, right_column));
+
+        nodes.insert(
+            name.to_string(),
+            Node {
+                name: name.to_string(),
                 left: left.to_string(),
                 right: right.to_string(),
-            };
-            (name, node)
-        })
-        .collect::<HashMap<_, _>>()
+            },
+        );
+    }
+
+    // Every node we can walk to must exist, so the walk itself never has to fail
+    if let Some((name, line, column)) = references
+        .into_iter()
+        .find(|(name, _, _)| !nodes.contains_key(name))
+    {
+        return Err(ParseError {
+            line,
+            column,
+            message: format!("node '{}' is referenced but never defined", name),
+        });
+    }
+
+    Ok(nodes)
+}
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Left,
+    Right,
 }
 
-fn parse_instructions(input: &str) -> &[u8] {
-    let instr_str = input.lines().filter(|l| !l.is_empty()).nth(0).unwrap();
-    instr_str.as_bytes()
+fn parse_instructions(input: &str) -> Result<Vec<Direction>, ParseError> {
+    let Some((line_number, line)) = non_blank_lines(input).next() else {
+        return Err(ParseError {
+            line: 1,
+            column: 1,
+            message: "expected instructions, found end of input".to_string(),
+        });
+    };
+
+    let indent = line.len() - line.trim_start().len();
+    line.trim()
+        .char_indices()
+        .map(|(i, c)| match c {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(ParseError {
+                line: line_number,
+                column: indent + i + 1,
+                message: format!("expected 'L' or 'R', found '{}'", c),
+            }),
+        })
+        .collect()
 }
 
 fn part_1(input: &str) -> u32 {
-    let instrs = parse_instructions(input);
-    let nodes = parse_nodes(input);
+    let instrs = parse_instructions(input).unwrap_or_else(|e| panic!("invalid input: {}", e));
+    let nodes = parse_nodes(input).unwrap_or_else(|e| panic!("invalid input: {}", e));
 
     let mut direction_i = 0;
     let mut steps = 0;
-    let mut curr_node = nodes.get("AAA").unwrap();
+    let mut curr_node = nodes
+        .get("AAA")
+        .expect("invalid input: there is no start node 'AAA'");
+    if !nodes.contains_key("ZZZ") {
+        panic!("invalid input: there is no end node 'ZZZ'");
+    }
+
+    // After this many steps a (node, instruction) state has repeated, so we walk in circles
+    let max_steps = nodes.len() * instrs.len();
 
     while curr_node.name != "ZZZ" {
-        let direction = instrs[direction_i] as char;
+        if steps as usize >= max_steps {
+            panic!("invalid input: 'ZZZ' can't be reached from 'AAA'");
+        }
 
-        let next_node_name: String = match direction {
-            'L' => curr_node.left.clone(),
-            _ => curr_node.right.clone(),
+        let next_node_name: String = match instrs[direction_i] {
+            Direction::Left => curr_node.left.clone(),
+            Direction::Right => curr_node.right.clone(),
         };
 
         curr_node = nodes.get(&next_node_name).unwrap();
@@ -79,12 +243,11 @@ fn part_1(input: &str) -> u32 {
 }
 
 fn part_2(input: &str) -> u64 {
-    let instrs = parse_instructions(input);
-    let nodes = parse_nodes(input);
+    let instrs = parse_instructions(input).unwrap_or_else(|e| panic!("invalid input: {}", e));
+    let nodes = parse_nodes(input).unwrap_or_else(|e| panic!("invalid input: {}", e));
 
     let all_starting_nodes = nodes
         .values()
-        .into_iter()
         .filter(|n| n.name.ends_with("A"))
         .collect::<Vec<&Node>>();
 
@@ -96,11 +259,9 @@ fn part_2(input: &str) -> u64 {
         let mut step = 0;
 
         while !curr_node.name.ends_with("Z") {
-            let direction = instrs[direction_i] as char;
-
-            let next_node_name: String = match direction {
-                'L' => curr_node.left.clone(),
-                _ => curr_node.right.clone(),
+            let next_node_name: String = match instrs[direction_i] {
+                Direction::Left => curr_node.left.clone(),
+                Direction::Right => curr_node.right.clone(),
             };
 
             let next_node = nodes.get(&next_node_name).unwrap();
@@ -118,10 +279,7 @@ fn part_2(input: &str) -> u64 {
         steps.push(step);
     }
 
-    steps
-        .into_iter()
-        .reduce(|acc, curr| lcm(acc, curr))
-        .unwrap()
+    steps.into_iter().reduce(lcm).unwrap()
 }
 
 #[cfg(test)]
@@ -169,4 +327,71 @@ XXX = (XXX, XXX)
     fn test_part_2() {
         assert_eq!(part_2(SAMPLE_DATA_3), 6);
     }
+
+    #[test]
+    fn test_parse_nodes() {
+        let nodes = parse_nodes(
+            r#"
+LR
+
+START   =(LEFTNODE ,  ZZZ)
+LEFTNODE = ( ZZZ,START )
+ZZZ = (ZZZ, ZZZ)
+"#,
+        )
+        .unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes["START"].left, "LEFTNODE");
+        assert_eq!(nodes["LEFTNODE"].right, "START");
+
+        assert_eq!(
+            parse_nodes("LR\n\nAAA = (BBB CCC)\n").unwrap_err(),
+            ParseError {
+                line: 3,
+                column: 12,
+                message: "expected ',', found 'C'".to_string()
+            }
+        );
+        assert_eq!(
+            parse_nodes("LR\n\nAAA = (AAA, BBB)\n").unwrap_err(),
+            ParseError {
+                line: 3,
+                column: 13,
+                message: "node 'BBB' is referenced but never defined".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no start node 'AAA'")]
+    fn test_missing_start_node() {
+        part_1("L\n\nBBB = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "'ZZZ' can't be reached from 'AAA'")]
+    fn test_unreachable_end_node() {
+        part_1("LR\n\nAAA = (BBB, AAA)\nBBB = (AAA, BBB)\nZZZ = (ZZZ, ZZZ)\n");
+    }
+
+    #[test]
+    fn test_parse_instructions() {
+        // A whitespace-only line before the instructions is skipped like any blank line
+        let input = "  \nLR\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(
+            parse_instructions(input),
+            Ok(vec![Direction::Left, Direction::Right])
+        );
+        assert_eq!(part_1(input), 1);
+
+        assert_eq!(
+            parse_instructions("LXR\n\nAAA = (AAA, AAA)\n").unwrap_err(),
+            ParseError {
+                line: 1,
+                column: 2,
+                message: "expected 'L' or 'R', found 'X'".to_string()
+            }
+        );
+        assert!(parse_instructions("\n \n").is_err());
+    }
 }
@@ -1,4 +1,7 @@
 use itertools::Itertools;
+use num::{BigInt, CheckedAdd, CheckedMul, CheckedSub, FromPrimitive, Integer};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 use std::{env, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
@@ -9,56 +12,182 @@ fn read_input_file(args: Vec<String>) -> String {
 
 fn main() {
     let input = read_input_file(env::args().collect());
-    let (result_part_1, result_part_2) = both_parts(&input);
 
-    println!("{:?}", result_part_1);
-    println!("{:?}", result_part_2);
+    // Try with plain i64 first, only fall back to BigInt when that overflows
+    match extrapolation_sums::<i64>(&input) {
+        Ok((result_part_1, result_part_2)) => {
+            println!("{:?}", result_part_1);
+            println!("{:?}", result_part_2);
+        }
+        Err(ExtrapolationError::Overflow) => {
+            let (result_part_1, result_part_2) = extrapolation_sums::<BigInt>(&input)
+                .unwrap_or_else(|e| panic!("cannot extrapolate input: {}", e));
+            println!("{}", result_part_1);
+            println!("{}", result_part_2);
+        }
+        Err(e) => panic!("cannot extrapolate input: {}", e),
+    }
+}
+
+/// The integer types the difference table can be built on, e.g. `i64` or `BigInt`
+trait Number:
+    Integer + CheckedAdd + CheckedSub + CheckedMul + FromPrimitive + FromStr + Clone + Debug
+{
+}
+
+impl<T> Number for T where
+    T: Integer + CheckedAdd + CheckedSub + CheckedMul + FromPrimitive + FromStr + Clone + Debug
+{
 }
 
-fn extrapolate_sequence(sequence: Vec<i64>) -> (i64, i64) {
-    // If all numbers are 0, we are done!
-    if (&sequence).into_iter().all(|num| *num == 0) {
-        return (0, 0);
+#[derive(Debug, PartialEq)]
+enum ExtrapolationError {
+    /// An intermediate value does not fit in the number type
+    Overflow,
+    /// The differences never become all zeros, so the sequence of `length` numbers
+    /// is not generated by a polynomial of degree < `length - 1`
+    NotPolynomial { length: usize },
+}
+
+impl fmt::Display for ExtrapolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtrapolationError::Overflow => write!(f, "arithmetic overflow"),
+            ExtrapolationError::NotPolynomial { length } => write!(
+                f,
+                "sequence of {} numbers is not a polynomial of degree < {}",
+                length,
+                length.saturating_sub(1)
+            ),
+        }
     }
+}
 
-    let last_number = (&sequence).last().unwrap();
-    let first_number = (&sequence).first().unwrap();
+/// All rows of differences of a sequence, starting with the sequence itself and
+/// ending with the first row that consists of only zeros.
+#[derive(Debug)]
+struct DifferenceTable<T> {
+    rows: Vec<Vec<T>>,
+}
 
-    let next_sequence = (&sequence)
-        .into_iter()
-        .tuple_windows() // Takes the current and next value, puts them in a tuple
-        .map(|(cur, next)| next - cur) // Substract the current value from the next
-        .collect_vec();
+impl<T: Number> DifferenceTable<T> {
+    fn new(sequence: &[T]) -> Result<Self, ExtrapolationError> {
+        let mut rows = vec![sequence.to_vec()];
 
-    let (next_forward_extrapolation, next_backward_extrapolation) =
-        extrapolate_sequence(next_sequence);
+        loop {
+            let row = rows.last().unwrap();
+            if row.is_empty() {
+                return Err(ExtrapolationError::NotPolynomial {
+                    length: sequence.len(),
+                });
+            }
+            if row.iter().all(|num| num.is_zero()) {
+                break;
+            }
 
-    (
-        *last_number + next_forward_extrapolation,
-        *first_number - next_backward_extrapolation,
-    )
+            let next_row = row
+                .iter()
+                .tuple_windows() // Takes the current and next value, puts them in a tuple
+                .map(|(cur, next)| next.checked_sub(cur)) // Substract the current value from the next
+                .collect::<Option<Vec<T>>>()
+                .ok_or(ExtrapolationError::Overflow)?;
+            rows.push(next_row);
+        }
+
+        Ok(Self { rows })
+    }
+
+    /// The degree of the polynomial that generates the sequence: the number of rows
+    /// with non-zero values, minus one. A sequence of only zeros has degree 0.
+    fn degree(&self) -> usize {
+        (self.rows.len() - 1).saturating_sub(1)
+    }
+
+    /// The value at (0-based) `index` of the sequence, which can be before the start
+    /// or past the end. Uses Newton's forward difference formula on the first column:
+    /// `a(t) = sum(C(t, j) * Δʲa(0))` for `j` in `0..=degree`.
+    fn value_at(&self, index: i64) -> Result<T, ExtrapolationError> {
+        let index = T::from_i64(index).ok_or(ExtrapolationError::Overflow)?;
+        let mut binomial = T::one(); // C(index, 0)
+        let mut value = T::zero();
+        let degree = self.degree();
+
+        for (j, row) in self.rows.iter().take(degree + 1).enumerate() {
+            let term = binomial
+                .checked_mul(&row[0])
+                .ok_or(ExtrapolationError::Overflow)?;
+            value = value
+                .checked_add(&term)
+                .ok_or(ExtrapolationError::Overflow)?;
+
+            // The last term doesn't need the next binomial, which might not fit
+            if j == degree {
+                break;
+            }
+
+            // C(t, j + 1) = C(t, j) * (t - j) / (j + 1), which always divides exactly
+            let j = T::from_usize(j).ok_or(ExtrapolationError::Overflow)?;
+            let factor = index.checked_sub(&j).ok_or(ExtrapolationError::Overflow)?;
+            binomial = binomial
+                .checked_mul(&factor)
+                .ok_or(ExtrapolationError::Overflow)?
+                / (j + T::one());
+        }
+
+        Ok(value)
+    }
+
+    /// The value `steps` positions after the last number of the sequence
+    fn extrapolate_forward(&self, steps: usize) -> Result<T, ExtrapolationError> {
+        let index = (self.rows[0].len() - 1)
+            .checked_add(steps)
+            .and_then(|i| i64::try_from(i).ok())
+            .ok_or(ExtrapolationError::Overflow)?;
+        self.value_at(index)
+    }
+
+    /// The value `steps` positions before the first number of the sequence
+    fn extrapolate_backward(&self, steps: usize) -> Result<T, ExtrapolationError> {
+        let index = i64::try_from(steps).map_err(|_| ExtrapolationError::Overflow)?;
+        self.value_at(-index)
+    }
 }
 
-fn parse_input(input: &str) -> Vec<Vec<i64>> {
+fn parse_input<T: FromStr>(input: &str) -> Vec<Vec<T>>
+where
+    T::Err: Debug,
+{
     input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .map(|line| {
-            line.split(" ")
-                .map(|c| c.parse::<i64>().unwrap())
+            line.split(' ')
+                .map(|c| c.parse::<T>().unwrap())
                 .collect_vec()
         })
         .collect_vec()
 }
 
-fn both_parts(input: &str) -> (i64, i64) {
-    let sequences = parse_input(input);
-    let extrapolation_sums: (i64, i64) = sequences
+fn extrapolation_sums<T: Number>(input: &str) -> Result<(T, T), ExtrapolationError>
+where
+    T::Err: Debug,
+{
+    parse_input::<T>(input)
         .into_iter()
-        .map(|s| extrapolate_sequence(s))
-        .fold((0, 0), |acc, curr| (acc.0 + curr.0, acc.1 + curr.1));
+        .try_fold((T::zero(), T::zero()), |acc, s| {
+            let table = DifferenceTable::new(&s)?;
+            let forward = table.extrapolate_forward(1)?;
+            let backward = table.extrapolate_backward(1)?;
 
-    extrapolation_sums
+            Ok((
+                acc.0
+                    .checked_add(&forward)
+                    .ok_or(ExtrapolationError::Overflow)?,
+                acc.1
+                    .checked_add(&backward)
+                    .ok_or(ExtrapolationError::Overflow)?,
+            ))
+        })
 }
 
 #[cfg(test)]
@@ -73,11 +202,85 @@ mod tests_09 {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(both_parts(SAMPLE_DATA).0, 114);
+        assert_eq!(extrapolation_sums::<i64>(SAMPLE_DATA).unwrap().0, 114);
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(both_parts(SAMPLE_DATA).1, 2);
+        assert_eq!(extrapolation_sums::<i64>(SAMPLE_DATA).unwrap().1, 2);
+    }
+
+    #[test]
+    fn test_difference_table() {
+        let table = DifferenceTable::new(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![10, 13, 16, 21, 30, 45],
+                vec![3, 3, 5, 9, 15],
+                vec![0, 2, 4, 6],
+                vec![2, 2, 2],
+                vec![0, 0],
+            ]
+        );
+        assert_eq!(table.degree(), 3);
+        assert_eq!(
+            DifferenceTable::new(&[0, 3, 6, 9, 12, 15])
+                .unwrap()
+                .degree(),
+            1
+        );
+        assert_eq!(DifferenceTable::new(&[7, 7, 7]).unwrap().degree(), 0);
+    }
+
+    #[test]
+    fn test_extrapolate_many_steps() {
+        // The triangular numbers: a(t) = (t + 1)(t + 2) / 2
+        let table = DifferenceTable::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(table.extrapolate_forward(1), Ok(28));
+        assert_eq!(table.extrapolate_forward(995), Ok(501501));
+        assert_eq!(table.extrapolate_backward(1), Ok(0));
+        assert_eq!(table.extrapolate_backward(5), Ok(6));
+
+        let table = DifferenceTable::new(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(table.extrapolate_forward(1), Ok(68));
+        assert_eq!(table.extrapolate_backward(1), Ok(5));
+        assert_eq!(table.value_at(2), Ok(16));
+
+        // Only the binomials of the terms that are used have to fit
+        let table = DifferenceTable::new(&[0_i64, 1, 2]).unwrap();
+        assert_eq!(table.extrapolate_forward(5_000_000_000), Ok(5_000_000_002));
+        assert_eq!(
+            table.extrapolate_backward(5_000_000_000),
+            Ok(-5_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_not_a_polynomial() {
+        assert_eq!(
+            DifferenceTable::new(&[1, 2, 4, 8, 16]).unwrap_err(),
+            ExtrapolationError::NotPolynomial { length: 5 }
+        );
+        assert_eq!(
+            DifferenceTable::<i64>::new(&[]).unwrap_err(),
+            ExtrapolationError::NotPolynomial { length: 0 }
+        );
+    }
+
+    #[test]
+    fn test_overflow_and_bigint() {
+        let input = "0 4000000000000000000 8000000000000000000\n";
+        assert_eq!(
+            extrapolation_sums::<i64>(input).unwrap_err(),
+            ExtrapolationError::Overflow
+        );
+        assert_eq!(
+            extrapolation_sums::<BigInt>(input),
+            Ok((
+                BigInt::from(12_000_000_000_000_000_000_i128),
+                BigInt::from(-4_000_000_000_000_000_000_i128)
+            ))
+        );
     }
 }
@@ -8,7 +8,30 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // `--render` draws the universe expanded as in part 1, `--render=X,Y` expands
+    // the empty columns X times and the empty rows Y times instead
+    for flag in flags {
+        let (x_multiplier, y_multiplier) = match flag.as_str() {
+            "--render" => (2, 2),
+            _ => flag
+                .strip_prefix("--render=")
+                .and_then(|factors| factors.split_once(','))
+                .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                .unwrap_or_else(|| panic!("unknown flag {}", flag)),
+        };
+        match Universe::new(&input)
+            .expand(x_multiplier, y_multiplier)
+            .render()
+        {
+            Some(grid) => print!("{}", grid),
+            None => println!("Expanded universe is too large to render"),
+        }
+    }
+
     let result_part_1 = part_1(&input);
     println!("{:?}", result_part_1);
 
@@ -17,64 +40,163 @@ fn main() {
 }
 
 type Pos = (usize, usize);
-type U = usize;
+type ExpandedPos = (u128, u128);
+
+/// Expanded universes with more cells than this are not rendered
+const MAX_RENDER_CELLS: u128 = 1_000_000;
+
+struct Universe {
+    galaxies: Vec<Pos>,
+    width: usize,
+    height: usize,
+}
+
+impl Universe {
+    fn new(input: &str) -> Self {
+        let lines = input.split('\n').filter(|l| !l.is_empty()).collect_vec();
+        let galaxies = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter_map(move |(x, char)| match char {
+                        '#' => Some((x, y)),
+                        _ => None,
+                    })
+            })
+            .collect_vec();
+
+        Self {
+            galaxies,
+            width: lines.iter().map(|l| l.len()).max().unwrap_or(0),
+            height: lines.len(),
+        }
+    }
+
+    /// Replaces every empty column by `x_multiplier` empty columns, and every empty
+    /// row by `y_multiplier` empty rows.
+    fn expand(&self, x_multiplier: u128, y_multiplier: u128) -> ExpandedUniverse {
+        let (all_x, all_y): (Vec<usize>, Vec<usize>) = self.galaxies.iter().copied().unzip();
+
+        let expanded_x = expanded_coordinates(&all_x, self.width, x_multiplier);
+        let expanded_y = expanded_coordinates(&all_y, self.height, y_multiplier);
+
+        ExpandedUniverse {
+            galaxies: self
+                .galaxies
+                .iter()
+                .map(|(x, y)| (expanded_x[*x], expanded_y[*y]))
+                .collect_vec(),
+            width: expanded_x[self.width],
+            height: expanded_y[self.height],
+        }
+    }
+}
+
+struct ExpandedUniverse {
+    galaxies: Vec<ExpandedPos>,
+    width: u128,
+    height: u128,
+}
+
+impl ExpandedUniverse {
+    /// Length of the shortest path between two galaxies, by their (0-based) index in reading order,
+    /// or `None` if there is no galaxy with one of the indices
+    #[cfg(test)]
+    fn distance(&self, a: usize, b: usize) -> Option<u128> {
+        Some(manhattan_distance(
+            *self.galaxies.get(a)?,
+            *self.galaxies.get(b)?,
+        ))
+    }
+
+    fn sum_shortest_paths(&self) -> u128 {
+        let (all_x, all_y): (Vec<u128>, Vec<u128>) = self.galaxies.iter().copied().unzip();
+
+        // The manhattan distance is the sum of the distances per axis, which can be summed independently
+        sum_pairwise_differences(all_x) + sum_pairwise_differences(all_y)
+    }
+
+    /// Draws the expanded universe, or `None` if it is too large to draw
+    fn render(&self) -> Option<String> {
+        let cells = self.width.checked_mul(self.height);
+        if cells.is_none_or(|cells| cells > MAX_RENDER_CELLS) {
+            return None;
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut grid = vec![vec!['.'; width]; height];
+        for (x, y) in self.galaxies.iter() {
+            grid[*y as usize][*x as usize] = '#';
+        }
 
-fn manhattan_distance((ax, ay): Pos, (bx, by): Pos) -> usize {
+        Some(
+            grid.into_iter()
+                .map(|row| String::from_iter(row) + "\n")
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+fn manhattan_distance((ax, ay): ExpandedPos, (bx, by): ExpandedPos) -> u128 {
     ax.abs_diff(bx) + ay.abs_diff(by)
 }
 
-fn sum_shortest_paths(input: &str, expansion_multiplier: U) -> usize {
-    let (mut galaxies, all_x, all_y): (Vec<Pos>, Vec<U>, Vec<U>) = input
-        .split("\n")
-        .filter(|l| !l.is_empty())
-        .enumerate()
-        .flat_map(|(y, line)| {
-            return line
-                .chars()
-                .enumerate()
-                .filter_map(move |(x, char)| match char {
-                    '#' => Some(((x, y), x, y)),
-                    _ => None,
-                });
+/// Maps every coordinate `0..=size` on one axis to its coordinate after expansion:
+/// each empty row or column before it is replaced by `expansion_multiplier` of them,
+/// a multiplier of 0 removes them. The last entry is the expanded size itself.
+fn expanded_coordinates(
+    coordinates: &[usize],
+    size: usize,
+    expansion_multiplier: u128,
+) -> Vec<u128> {
+    let mut occupied = vec![false; size];
+    for c in coordinates {
+        occupied[*c] = true;
+    }
+
+    let mut num_empty_before: u128 = 0;
+    (0..=size)
+        .map(|c| {
+            let num_occupied_before = c as u128 - num_empty_before;
+            let expanded = num_occupied_before + num_empty_before * expansion_multiplier;
+            if c < size && !occupied[c] {
+                num_empty_before += 1;
+            }
+            expanded
         })
-        .multiunzip();
-
-    let width = (&all_x).into_iter().max().unwrap() + 1;
-    let height = (&all_y).into_iter().max().unwrap() + 1;
-
-    let empty_x = (0..width)
-        .into_iter()
-        .filter(|x| !all_x.contains(x))
-        .collect_vec();
-
-    let empty_y = (0..height)
-        .into_iter()
-        .filter(|y| !all_y.contains(y))
-        .collect_vec();
-
-    // Move all galaxies
-    for (x, y) in galaxies.iter_mut() {
-        let num_shift_right = empty_x.iter().filter(|&nx| *nx < *x).count();
-        let num_shift_down = empty_y.iter().filter(|&ny| *ny < *y).count();
-        *x += num_shift_right * (expansion_multiplier - 1);
-        *y += num_shift_down * (expansion_multiplier - 1);
+        .collect_vec()
+}
+
+/// Sum of `|a - b|` over all pairs, in O(n log n): after sorting, every value is
+/// larger than or equal to all values before it, so it contributes
+/// `value * i - (sum of the i values before it)`.
+fn sum_pairwise_differences(mut values: Vec<u128>) -> u128 {
+    values.sort_unstable();
+
+    let mut prefix_sum: u128 = 0;
+    let mut sum: u128 = 0;
+    for (i, value) in values.into_iter().enumerate() {
+        sum += value * i as u128 - prefix_sum;
+        prefix_sum += value;
     }
 
-    let sum_distances = (&galaxies)
-        .into_iter()
-        .combinations(2)
-        .fold(0, |acc, combination| {
-            acc + manhattan_distance(*combination[0], *combination[1])
-        });
+    sum
+}
 
-    sum_distances
+fn sum_shortest_paths(input: &str, expansion_multiplier: u128) -> u128 {
+    Universe::new(input)
+        .expand(expansion_multiplier, expansion_multiplier)
+        .sum_shortest_paths()
 }
 
-fn part_1(input: &str) -> usize {
+fn part_1(input: &str) -> u128 {
     sum_shortest_paths(input, 2)
 }
 
-fn part_2(input: &str) -> usize {
+fn part_2(input: &str) -> u128 {
     sum_shortest_paths(input, 1_000_000)
 }
 
@@ -100,5 +222,67 @@ mod tests_11 {
         assert_eq!(sum_shortest_paths(SAMPLE_DATA, 2), 374);
         assert_eq!(sum_shortest_paths(SAMPLE_DATA, 10), 1030);
         assert_eq!(sum_shortest_paths(SAMPLE_DATA, 100), 8410);
+        assert_eq!(
+            sum_shortest_paths(SAMPLE_DATA, 1_000_000_000_000_000_000_000),
+            82_000_000_000_000_000_000_210
+        );
+    }
+
+    #[test]
+    fn test_pair_distances() {
+        let universe = Universe::new(SAMPLE_DATA).expand(2, 2);
+
+        // The puzzle numbers the galaxies from 1, we index from 0
+        assert_eq!(universe.distance(4, 8), Some(9));
+        assert_eq!(universe.distance(0, 6), Some(15));
+        assert_eq!(universe.distance(2, 5), Some(17));
+        assert_eq!(universe.distance(7, 8), Some(5));
+
+        // Only the empty columns grow
+        let universe = Universe::new(SAMPLE_DATA).expand(10, 1);
+        assert_eq!(universe.distance(7, 8), Some(4 + 9));
+        assert_eq!(universe.distance(0, 6), Some(4 + 9 + 8));
+
+        assert_eq!(universe.distance(0, 9), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let universe = Universe::new(SAMPLE_DATA).expand(2, 2);
+        assert_eq!(
+            universe.render().unwrap(),
+            r#"....#........
+.........#...
+#............
+.............
+.............
+........#....
+.#...........
+............#
+.............
+.............
+.........#...
+#....#.......
+"#
+        );
+
+        assert_eq!(
+            Universe::new(SAMPLE_DATA).expand(1_000_000, 1).render(),
+            None
+        );
+
+        // Without the empty column and row
+        assert_eq!(
+            Universe::new("#..\n...\n..#\n").expand(0, 0).render(),
+            Some("#.\n.#\n".to_string())
+        );
+
+        // The number of cells doesn't fit in a u128
+        assert_eq!(
+            Universe::new("#..\n...\n..#\n")
+                .expand(1 << 70, 1 << 70)
+                .render(),
+            None
+        );
     }
 }
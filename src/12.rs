@@ -1,5 +1,3 @@
-use cached::proc_macro::cached;
-use cached::UnboundCache;
 use itertools::Itertools;
 use std::{env, fs};
 
@@ -10,7 +8,20 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // `--arrangements=N` prints up to N arrangements of every row, spread over all of them
+    for flag in flags {
+        match flag.strip_prefix("--arrangements=") {
+            Some(limit) => {
+                print_arrangements(&input, limit.parse().expect("limit should be a number"))
+            }
+            None => panic!("unknown flag {}", flag),
+        }
+    }
+
     let result_part_1 = part_1(&input);
     println!("{:?}", result_part_1);
 
@@ -18,127 +29,299 @@ fn main() {
     println!("{:?}", result_part_2);
 }
 
-fn part_1(input: &str) -> usize {
+fn part_1(input: &str) -> u128 {
     input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .map(|line| determine_number_of_arrangements(line, 1))
         .sum()
 }
 
-fn part_2(input: &str) -> usize {
+fn part_2(input: &str) -> u128 {
     input
-        .split("\n")
+        .split('\n')
         .filter(|l| !l.is_empty())
         .map(|line| determine_number_of_arrangements(line, 5))
         .sum()
 }
 
-fn cache_key(row: &str, config: &Vec<usize>) -> String {
-    format!("{}x{}x", row, config.into_iter().join("_"))
+/// If a group of `len` disabled springs (#) can start at `pos`, returns the position right
+/// after it. The whole group must fit without working springs (.) in it, and must be followed
+/// by the end of the row or a spot that can be a working spring.
+fn group_end(row: &[u8], pos: usize, len: usize) -> Option<usize> {
+    let end = pos + len;
+    if end <= row.len() && !row[pos..end].contains(&b'.') && row.get(end) != Some(&b'#') {
+        Some(end)
+    } else {
+        None
+    }
 }
 
-#[cached(
-    type = "UnboundCache<String, usize>",
-    create = "{ UnboundCache::new() }",
-    convert = r#"{ cache_key(row,config) }"#
-)]
-fn number_of_arrangements(row: &str, config: &Vec<usize>) -> usize {
-    let chars = row.chars().collect_vec();
+/// Dynamic programming table over (position in the row, index of the next group in the
+/// config). `table[pos][group]` holds the number of arrangements of `row[pos..]` that
+/// match `config[group..]`, and is filled from the end of the row to the start.
+/// The counts grow exponentially with the number of unknown springs, so they are u128's.
+fn arrangements_table(row: &[u8], config: &[usize]) -> Vec<Vec<u128>> {
+    let (n, m) = (row.len(), config.len());
 
-    if row.starts_with(".") {
-        let row_next_part = String::from_iter(&chars[1..]);
-        return number_of_arrangements(&row_next_part, &config);
-    }
+    // One extra position, because placing a group that ends the row also skips its separator
+    let mut table = vec![vec![0; m + 1]; n + 2];
+    table[n][m] = 1;
+    table[n + 1][m] = 1;
+
+    for pos in (0..n).rev() {
+        for group in 0..=m {
+            let mut arrangements: u128 = 0;
+
+            // Treat this spot as a working spring (.) and move on to the next one
+            if row[pos] != b'#' {
+                arrangements += table[pos + 1][group];
+            }
+
+            // Treat this spot as the start of the next group of disabled springs (#)
+            if row[pos] != b'.' && group < m {
+                if let Some(end) = group_end(row, pos, config[group]) {
+                    arrangements = arrangements
+                        .checked_add(table[end + 1][group + 1])
+                        .expect("number of arrangements should fit in a u128");
+                }
+            }
 
-    // The row is empty
-    if chars.len() == 0 {
-        // And the config too, yay! Possible
-        if config.len() == 0 {
-            return 1;
-        } else {
-            // Nope, that's not good.
-            return 0;
+            table[pos][group] = arrangements;
         }
     }
 
-    if config.len() == 0 {
-        // The config prescribes no more disabled springs, but we still have some, so this is not possible
-        if chars.contains(&'#') {
-            return 0;
-        } else {
-            // Only unknown or working springs remaining, possible!
-            return 1;
+    table
+}
+
+fn number_of_arrangements(row: &[u8], config: &[usize]) -> u128 {
+    arrangements_table(row, config)[0][0]
+}
+
+/// Iterates over all arrangements of a row, with every unknown spring (?) resolved to a working (.)
+/// or disabled (#) one, in sorted order. Every arrangement is looked up
+/// by its index in the counting table, so skipping ahead with `nth` or `step_by` is cheap, which
+/// makes it possible to sample rows with a huge number of arrangements.
+struct Arrangements<'a> {
+    row: &'a [u8],
+    config: &'a [usize],
+    table: Vec<Vec<u128>>,
+    next: u128,
+    count: u128,
+}
+
+impl<'a> Arrangements<'a> {
+    fn new(row: &'a [u8], config: &'a [usize]) -> Self {
+        let table = arrangements_table(row, config);
+        let count = table[0][0];
+        Self {
+            row,
+            config,
+            table,
+            next: 0,
+            count,
         }
     }
 
-    //Number of disabled springs to check for in this iteration
-    let num_disabled_springs = config[0];
+    /// Builds the arrangement with the given index, by walking the table and choosing the branch
+    /// (working or disabled) that contains the index at every spot
+    fn arrangement(&self, mut index: u128) -> String {
+        let (row, n) = (self.row, self.row.len());
+        let mut arrangement = String::with_capacity(n);
+        let (mut pos, mut group) = (0, 0);
 
-    // The row starts with a known damaged spring (#)
-    if chars[0] == '#' {
-        // The config prescribes more springs than spots are available, not possible:
-        if chars.len() < num_disabled_springs {
-            return 0;
-        }
+        while pos < n {
+            // Disabled springs (#) sort before working ones (.), so try starting a group first
+            let end = match (row[pos], self.config.get(group)) {
+                (b'.', _) | (_, None) => None,
+                (_, Some(len)) => group_end(row, pos, *len),
+            };
+            let num_when_disabled = end.map_or(0, |end| self.table[end + 1][group + 1]);
 
-        // There is a working spring (.) in the remaining spots of the config length, not possible:
-        if chars[..num_disabled_springs].contains(&'.') {
-            return 0;
-        }
+            if index < num_when_disabled {
+                let end = end.unwrap();
+                arrangement.push_str(&"#".repeat(end - pos));
+                if end < n {
+                    arrangement.push('.');
+                }
+                pos = end + 1;
+                group += 1;
+                continue;
+            }
+            index -= num_when_disabled;
 
-        // Only one disabled spring left, but the config prescribes differently, not possible:
-        if chars.len() == 1 && num_disabled_springs != 1 {
-            return 0;
+            arrangement.push('.');
+            pos += 1;
         }
 
-        // Character after the end of this part is a #, that is not possible (should be . or ?)
-        if chars.len() > num_disabled_springs && chars[num_disabled_springs] == '#' {
-            return 0;
+        arrangement
+    }
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.next >= self.count {
+            return None;
         }
 
-        if chars.len() == num_disabled_springs {
-            if config.len() == 1 {
-                // This is the last group, possible!
-                return 1;
-            } else {
-                // There is another config number, this is not possible
-                return 0;
-            }
+        let arrangement = self.arrangement(self.next);
+        self.next += 1;
+        Some(arrangement)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<String> {
+        self.next = self.next.saturating_add(n as u128);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count.saturating_sub(self.next);
+        match usize::try_from(remaining) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
         }
+    }
+}
 
-        // This config part looks good, move on to the next:
-        let row_next_part = String::from_iter(&chars[(num_disabled_springs + 1)..]);
-        return number_of_arrangements(&row_next_part, &config[1..].to_vec());
+/// Finds the unknown springs (?) that are the same in every arrangement, as (position, '.' or '#').
+/// For every spot it counts the arrangements in which that spot is part of a group, by combining
+/// the number of ways to fill the row before a group with the number of ways to fill the row after it.
+fn forced_cells(row: &[u8], config: &[usize]) -> Vec<(usize, char)> {
+    let (n, m) = (row.len(), config.len());
+    let after = arrangements_table(row, config);
+    let total = after[0][0];
+    if total == 0 {
+        return Vec::new();
     }
-    // The row starts with an unknown spring (?)
-    else {
-        // Replace the first spot with a working spring, and recursively check the rest:
-        let row_with_working = String::from_iter(&chars[1..]);
-        let num_arrangements_when_working = number_of_arrangements(&row_with_working, &config);
 
-        // Replace the first spot with a disabled spring, and recursively check the rest:
-        let row_with_disabled = format!("{}{}", "#", String::from_iter(&chars[1..]));
-        let num_arrangements_when_disabled = number_of_arrangements(&row_with_disabled, &config);
+    // `before[pos][group]`: number of ways to fill `row[..pos]` with `config[..group]`
+    let mut before = vec![vec![0; m + 1]; n + 2];
+    before[0][0] = 1;
+
+    // Number of arrangements in which a group starts or ends at each spot
+    let mut group_starts: Vec<u128> = vec![0; n + 1];
+    let mut group_ends: Vec<u128> = vec![0; n + 1];
 
-        return num_arrangements_when_working + num_arrangements_when_disabled;
+    for pos in 0..n {
+        for group in 0..=m {
+            let ways = before[pos][group];
+            if ways == 0 {
+                continue;
+            }
+
+            if row[pos] != b'#' {
+                before[pos + 1][group] += ways;
+            }
+
+            if row[pos] != b'.' && group < m {
+                if let Some(end) = group_end(row, pos, config[group]) {
+                    before[end + 1][group + 1] += ways;
+
+                    let arrangements = ways * after[end + 1][group + 1];
+                    group_starts[pos] += arrangements;
+                    group_ends[end] += arrangements;
+                }
+            }
+        }
     }
+
+    // Number of arrangements in which the current spot is disabled
+    let mut num_disabled: u128 = 0;
+    let mut forced = Vec::new();
+    for pos in 0..n {
+        num_disabled = num_disabled - group_ends[pos] + group_starts[pos];
+        if row[pos] == b'?' {
+            if num_disabled == total {
+                forced.push((pos, '#'));
+            } else if num_disabled == 0 {
+                forced.push((pos, '.'));
+            }
+        }
+    }
+
+    forced
 }
 
-fn determine_number_of_arrangements(line: &str, unfold: usize) -> usize {
-    let (row_str_raw, config_str_raw) = line.split_once(" ").unwrap();
+/// What goes between the copies of a row when unfolding it, the puzzle only uses `Unknown`
+#[derive(Debug, Clone, Copy)]
+enum Separator {
+    /// An unknown spring (?), as in the puzzle
+    Unknown,
+    /// A working spring (.), which makes every copy independent
+    #[cfg(test)]
+    Operational,
+    /// Nothing, the copies are directly concatenated
+    #[cfg(test)]
+    None,
+}
 
-    let row_str_vec = vec![row_str_raw; unfold];
-    let config_str_vec = vec![config_str_raw; unfold];
+fn parse_line(line: &str) -> (String, Vec<usize>) {
+    let (row, config_str) = line.split_once(' ').unwrap();
+    assert!(
+        row.bytes().all(|c| matches!(c, b'.' | b'#' | b'?')),
+        "row should only contain '.', '#' and '?': {}",
+        row
+    );
 
-    let row = row_str_vec.join("&");
-    let config_str = config_str_vec.join(",");
     let config = config_str
         .split(',')
         .map(|c| c.parse().unwrap())
         .collect_vec();
 
-    number_of_arrangements(&row, &config)
+    (row.to_string(), config)
+}
+
+/// Repeats the row `copies` times with the given separator in between, and the config `copies` times
+fn unfold(
+    row: &str,
+    config: &[usize],
+    copies: usize,
+    separator: Separator,
+) -> (String, Vec<usize>) {
+    let separator = match separator {
+        Separator::Unknown => "?",
+        #[cfg(test)]
+        Separator::Operational => ".",
+        #[cfg(test)]
+        Separator::None => "",
+    };
+
+    (vec![row; copies].join(separator), config.repeat(copies))
+}
+
+fn unfolded_number_of_arrangements(line: &str, copies: usize, separator: Separator) -> u128 {
+    let (row, config) = parse_line(line);
+    let (row, config) = unfold(&row, &config, copies, separator);
+    number_of_arrangements(row.as_bytes(), &config)
+}
+
+fn determine_number_of_arrangements(line: &str, unfold: usize) -> u128 {
+    unfolded_number_of_arrangements(line, unfold, Separator::Unknown)
+}
+
+/// Prints at most `limit` arrangements per line, evenly spread over all of them, followed by the
+/// row with all forced unknown springs filled in
+fn print_arrangements(input: &str, limit: usize) {
+    for line in input.split('\n').filter(|l| !l.is_empty()) {
+        let (row, config) = parse_line(line);
+        let arrangements = Arrangements::new(row.as_bytes(), &config);
+        let count = arrangements.count;
+        println!("{} ({} arrangements)", line, count);
+
+        let step = count.div_ceil(limit.max(1) as u128).max(1);
+        let step = usize::try_from(step).unwrap_or(usize::MAX);
+        for arrangement in arrangements.step_by(step).take(limit) {
+            println!("  {}", arrangement);
+        }
+
+        let mut hint = row.into_bytes();
+        for (pos, char) in forced_cells(&hint, &config) {
+            hint[pos] = char as u8;
+        }
+        println!("  forced: {}", String::from_utf8(hint).unwrap());
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +387,98 @@ mod tests_12 {
         );
     }
 
+    #[test]
+    fn test_unfold_separators() {
+        assert_eq!(
+            unfolded_number_of_arrangements("?? 1", 2, Separator::Unknown),
+            6
+        );
+        assert_eq!(
+            unfolded_number_of_arrangements("?? 1", 2, Separator::Operational),
+            4
+        );
+        assert_eq!(
+            unfolded_number_of_arrangements("?? 1", 2, Separator::None),
+            3
+        );
+        assert_eq!(
+            unfolded_number_of_arrangements(".??..??...?##. 1,1,3", 5, Separator::Operational),
+            4_u128.pow(5)
+        );
+
+        // Does not fit in a u64
+        assert_eq!(
+            unfolded_number_of_arrangements(".??..??...?##. 1,1,3", 40, Separator::Operational),
+            4_u128.pow(40)
+        );
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let config = [3, 2, 1];
+        let arrangements = Arrangements::new(b"?###????????", &config).collect_vec();
+        assert_eq!(
+            arrangements,
+            vec![
+                ".###.##.#...",
+                ".###.##..#..",
+                ".###.##...#.",
+                ".###.##....#",
+                ".###..##.#..",
+                ".###..##..#.",
+                ".###..##...#",
+                ".###...##.#.",
+                ".###...##..#",
+                ".###....##.#",
+            ]
+        );
+
+        // Every arrangement must be counted exactly once
+        for line in SAMPLE_DATA.split('\n').filter(|l| !l.is_empty()) {
+            let (row, config) = parse_line(line);
+            let arrangements = Arrangements::new(row.as_bytes(), &config).collect_vec();
+            assert_eq!(
+                arrangements.len() as u128,
+                number_of_arrangements(row.as_bytes(), &config)
+            );
+            assert!(arrangements.iter().all_unique());
+        }
+
+        // Sampling skips straight to an arrangement
+        let (row, config) = parse_line("?###???????? 3,2,1");
+        let (row, config) = unfold(&row, &config, 5, Separator::Unknown);
+        let mut arrangements = Arrangements::new(row.as_bytes(), &config);
+        assert_eq!(arrangements.size_hint(), (506250, Some(506250)));
+        let last = arrangements.nth(506249).unwrap();
+        assert_eq!(last.len(), row.len());
+        assert_eq!(
+            last.split('.')
+                .filter(|g| !g.is_empty())
+                .map(|g| g.len())
+                .collect_vec(),
+            config
+        );
+        assert!(last.ends_with(".###....##.#"));
+        assert_eq!(arrangements.next(), None);
+    }
+
+    #[test]
+    fn test_forced_cells() {
+        assert_eq!(
+            forced_cells(b"???.###", &[1, 1, 3]),
+            vec![(0, '#'), (1, '.'), (2, '#')]
+        );
+        assert_eq!(
+            forced_cells(b"?###????????", &[3, 2, 1]),
+            vec![(0, '.'), (4, '.')]
+        );
+        assert_eq!(
+            forced_cells(b"????????", &[3, 3]),
+            vec![(1, '#'), (2, '#'), (5, '#'), (6, '#')]
+        );
+        assert_eq!(forced_cells(b"??", &[3]), Vec::new());
+    }
+
     #[test]
     fn test_part_1() {
         assert_eq!(part_1(SAMPLE_DATA), 21);
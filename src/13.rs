@@ -1,5 +1,6 @@
 use itertools::Itertools;
-use std::{env, fs};
+use std::collections::HashSet;
+use std::{env, fmt, fs};
 
 fn read_input_file(args: Vec<String>) -> String {
     let default_input_filename = &String::from("input/13");
@@ -8,63 +9,115 @@ fn read_input_file(args: Vec<String>) -> String {
 }
 
 fn main() {
-    let input = read_input_file(env::args().collect());
-    let result_part_1 = part_1(&input);
-    println!("{:?}", result_part_1);
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    // Draws every pattern once for each mirror line, with or without smudge
+    for flag in flags {
+        let mode = match flag.as_str() {
+            "--render" => RenderMode::Ansi,
+            "--render=plain" => RenderMode::Plain,
+            _ => panic!("unknown flag {}", flag),
+        };
+
+        for pattern in parse_input(&input) {
+            for reflection in pattern
+                .find_all_reflections(0)
+                .iter()
+                .chain(pattern.find_all_reflections(1).iter())
+            {
+                println!("{}", pattern.render(reflection, &mode));
+            }
+        }
+    }
+
+    match part_1(&input) {
+        Ok(result_part_1) => println!("{:?}", result_part_1),
+        Err(e) => println!("invalid input: {}", e),
+    }
+
+    match part_2(&input) {
+        Ok(result_part_2) => println!("{:?}", result_part_2),
+        Err(e) => println!("invalid input: {}", e),
+    }
+}
 
-    let result_part_2 = part_2(&input);
-    println!("{:?}", result_part_2);
+/// A pattern that doesn't have exactly one mirror line, which the puzzle promises
+#[derive(Debug, PartialEq)]
+struct ReflectionError {
+    /// 1-based, in input order
+    pattern: usize,
+    num_differences: usize,
+    found: usize,
 }
 
-fn part_1(input: &str) -> usize {
-    parse_input(input)
-        .iter()
-        .filter_map(|pattern| pattern.find_reflection(None))
-        .fold(0, |acc, res| acc + res.score())
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "pattern {} has {} mirror lines with {} differences, expected exactly one",
+            self.pattern, self.found, self.num_differences
+        )
+    }
 }
 
-fn part_2(input: &str) -> usize {
+fn sum_of_scores(input: &str, num_differences: usize) -> Result<usize, ReflectionError> {
     parse_input(input)
         .iter()
-        .filter_map(|pattern| {
-            let orig_reflection = pattern.find_reflection(None);
-
-            for y in 0..pattern.height {
-                for x in 0..pattern.width {
-                    let changed_pattern = pattern.with_flipped_char_at(x, y);
-                    match changed_pattern.find_reflection(orig_reflection.as_ref()) {
-                        Some(new_result) => return Some(new_result),
-                        None => {}
-                    };
-                }
-            }
-            None
+        .enumerate()
+        .map(|(i, pattern)| {
+            pattern
+                .the_reflection(num_differences)
+                .map(|reflection| reflection.score())
+                .map_err(|reflections| ReflectionError {
+                    pattern: i + 1,
+                    num_differences,
+                    found: reflections.len(),
+                })
         })
-        .fold(0, |acc, res| acc + res.score())
+        .sum()
+}
+
+fn part_1(input: &str) -> Result<usize, ReflectionError> {
+    sum_of_scores(input, 0)
+}
+
+fn part_2(input: &str) -> Result<usize, ReflectionError> {
+    // The smudge is the one cell that differs between the two sides of the new reflection line
+    sum_of_scores(input, 1)
 }
 
 fn parse_input(input: &str) -> Vec<Pattern> {
     input
         .split("\n\n")
-        .map(|pattern_str| Pattern::new(pattern_str))
+        .filter(|pattern_str| !pattern_str.trim().is_empty())
+        .map(Pattern::new)
         .collect_vec()
 }
 
+type Pos = (usize, usize);
+
+/// A cell that differs between two mirrored lines: its index along the lines, and the
+/// indices of both lines
+type LineDifference = (usize, usize, usize);
+
+#[derive(Debug, PartialEq)]
 enum ReflectionOrientation {
     H,
     V,
 }
 
+#[derive(Debug, PartialEq)]
 struct ReflectionResult {
     i: usize,
     orientation: ReflectionOrientation,
+    /// The (x, y) positions of the mirrored cells that differ. Fixing either cell of a pair
+    /// (the smudge) removes that difference.
+    smudges: Vec<(Pos, Pos)>,
 }
 
 impl ReflectionResult {
-    fn new(i: usize, orientation: ReflectionOrientation) -> Self {
-        Self { i, orientation }
-    }
-
     fn score(&self) -> usize {
         match self.orientation {
             ReflectionOrientation::H => self.i * 100,
@@ -73,119 +126,169 @@ impl ReflectionResult {
     }
 }
 
+enum RenderMode {
+    /// Smudges are highlighted in red
+    Ansi,
+    /// Smudges are replaced by `*`, for comparing in tests
+    Plain,
+}
+
+/// A pattern with every row and every column stored as a bitmask, where bit `n` is set when
+/// the cell at index `n` of that row or column is a rock (#)
 #[derive(Debug)]
 struct Pattern {
-    pattern: String,
-    width: usize,
-    height: usize,
+    rows: Vec<u64>,
+    columns: Vec<u64>,
 }
 
 impl Pattern {
     fn new(pattern_str: &str) -> Self {
-        let pattern = pattern_str.trim().to_string();
-        let height = pattern.chars().filter(|c| c == &'\n').count() + 1;
-        let width = pattern.chars().take_while(|c| c != &'\n').count();
-
-        Self {
-            pattern,
-            height,
-            width,
-        }
-    }
-
-    fn rotated(&self) -> Self {
-        let lines = self
-            .pattern
-            .split("\n")
+        let lines = pattern_str
+            .trim()
+            .split('\n')
             .filter(|l| !l.is_empty())
             .collect_vec();
-
-        let mut rotated_pattern = String::new();
-        for x in 0..self.width {
-            for line in lines.iter() {
-                rotated_pattern += &line.chars().nth(x).unwrap().to_string();
+        let width = lines[0].len();
+        if let Some(line) = lines.iter().find(|l| l.len() != width) {
+            panic!("all rows should be {} wide: {}", width, line);
+        }
+        assert!(
+            width <= 64 && lines.len() <= 64,
+            "patterns should be at most 64x64"
+        );
+
+        let mut rows = vec![0; lines.len()];
+        let mut columns = vec![0; width];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, char) in line.bytes().enumerate() {
+                if char == b'#' {
+                    rows[y] |= 1 << x;
+                    columns[x] |= 1 << y;
+                }
             }
-            rotated_pattern += "\n";
         }
 
-        return Pattern::new(&rotated_pattern);
+        Self { rows, columns }
     }
 
-    fn with_flipped_char_at(&self, flip_x: usize, flip_y: usize) -> Self {
-        let mut new_pattern = String::new();
-
-        for (y, line) in self
-            .pattern
-            .split("\n")
-            .filter(|l| !l.is_empty())
-            .enumerate()
-        {
-            for (x, char) in line.chars().enumerate() {
-                if (flip_x, flip_y) == (x, y) {
-                    if char == '#' {
-                        new_pattern += ".";
-                    } else {
-                        new_pattern += "#";
+    /// Finds every line between `lines[i - 1]` and `lines[i]` for which the mirrored pairs of
+    /// lines differ in exactly `num_differences` cells in total. Returns every `i` with its differences.
+    fn find_reflection_positions(
+        lines: &[u64],
+        num_differences: usize,
+    ) -> Vec<(usize, Vec<LineDifference>)> {
+        (1..lines.len())
+            .filter_map(|i| {
+                let mut differences = Vec::new();
+                for (before, after) in (0..i).rev().zip(i..lines.len()) {
+                    let mut diff = lines[before] ^ lines[after];
+                    while diff != 0 {
+                        differences.push((diff.trailing_zeros() as usize, before, after));
+                        if differences.len() > num_differences {
+                            return None;
+                        }
+                        diff &= diff - 1;
                     }
-                } else {
-                    new_pattern += &char.to_string();
                 }
-            }
-            new_pattern += "\n";
-        }
 
-        return Pattern::new(&new_pattern);
+                (differences.len() == num_differences).then_some((i, differences))
+            })
+            .collect_vec()
     }
 
-    fn find_reflection_position(&self, skip_position: Option<usize>) -> Option<usize> {
-        let mut last_line = "";
-        let lines = self
-            .pattern
-            .split("\n")
-            .filter(|l| !l.is_empty())
-            .collect_vec();
+    /// All horizontal and (after those) vertical mirror lines whose sides differ in exactly
+    /// `max_differences` cells
+    fn find_all_reflections(&self, max_differences: usize) -> Vec<ReflectionResult> {
+        let horizontal = Self::find_reflection_positions(&self.rows, max_differences)
+            .into_iter()
+            .map(|(y, differences)| ReflectionResult {
+                i: y,
+                orientation: ReflectionOrientation::H,
+                smudges: differences
+                    .into_iter()
+                    .map(|(x, before, after)| ((x, before), (x, after)))
+                    .collect_vec(),
+            });
+
+        let vertical = Self::find_reflection_positions(&self.columns, max_differences)
+            .into_iter()
+            .map(|(x, differences)| ReflectionResult {
+                i: x,
+                orientation: ReflectionOrientation::V,
+                smudges: differences
+                    .into_iter()
+                    .map(|(y, before, after)| ((before, y), (after, y)))
+                    .collect_vec(),
+            });
+
+        horizontal.chain(vertical).collect_vec()
+    }
 
-        'outer: for (y, line) in lines.iter().enumerate() {
-            if skip_position == Some(y) {
-                last_line = line;
-                continue;
-            }
+    fn is_rock(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & (1 << x) != 0
+    }
 
-            if line == &last_line {
-                for i in 0..(y) {
-                    if (y + i) >= lines.len() {
-                        break;
-                    }
+    /// Draws the pattern with `><` above and below a vertical mirror line, or `v^` on both sides
+    /// of a horizontal one, and the cells of every smudge pair highlighted
+    fn render(&self, reflection: &ReflectionResult, mode: &RenderMode) -> String {
+        let (width, height) = (self.columns.len(), self.rows.len());
+        let smudges = reflection
+            .smudges
+            .iter()
+            .flat_map(|(a, b)| [*a, *b])
+            .collect::<HashSet<Pos>>();
+
+        let vertical_markers = format!(
+            " {}><{} \n",
+            " ".repeat(reflection.i - 1),
+            " ".repeat(width - reflection.i - 1)
+        );
+
+        let mut output = String::new();
+        if reflection.orientation == ReflectionOrientation::V {
+            output += &vertical_markers;
+        }
 
-                    if lines[y + i] != lines[y - i - 1] {
-                        last_line = line;
-                        continue 'outer;
-                    }
+        for y in 0..height {
+            let margin = match reflection.orientation {
+                ReflectionOrientation::H if y + 1 == reflection.i => 'v',
+                ReflectionOrientation::H if y == reflection.i => '^',
+                _ => ' ',
+            };
+            output.push(margin);
+
+            for x in 0..width {
+                let char = if self.is_rock(x, y) { '#' } else { '.' };
+                if !smudges.contains(&(x, y)) {
+                    output.push(char);
+                    continue;
+                }
+                match mode {
+                    RenderMode::Ansi => output += &format!("\x1b[1;31m{}\x1b[0m", char),
+                    RenderMode::Plain => output.push('*'),
                 }
-                return Some(y);
             }
 
-            last_line = line;
+            output.push(margin);
+            output.push('\n');
         }
 
-        return None;
-    }
+        if reflection.orientation == ReflectionOrientation::V {
+            output += &vertical_markers;
+        }
 
-    fn find_reflection(&self, skip_result: Option<&ReflectionResult>) -> Option<ReflectionResult> {
-        let (skip_y, skip_x) = match skip_result {
-            Some(result) => match result.orientation {
-                ReflectionOrientation::H => (Some(result.i), None),
-                ReflectionOrientation::V => (None, Some(result.i)),
-            },
-            None => (None, None),
-        };
+        output
+    }
 
-        if let Some(y) = self.find_reflection_position(skip_y) {
-            return Some(ReflectionResult::new(y, ReflectionOrientation::H));
-        } else if let Some(x) = self.rotated().find_reflection_position(skip_x) {
-            return Some(ReflectionResult::new(x, ReflectionOrientation::V));
-        } else {
-            return None;
+    /// The only mirror line with this number of differences, or all of them if there are none or several
+    fn the_reflection(
+        &self,
+        num_differences: usize,
+    ) -> Result<ReflectionResult, Vec<ReflectionResult>> {
+        let mut reflections = self.find_all_reflections(num_differences);
+        match reflections.len() {
+            1 => Ok(reflections.remove(0)),
+            _ => Err(reflections),
         }
     }
 }
@@ -214,11 +317,119 @@ mod tests_13 {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(SAMPLE_DATA), 405);
+        assert_eq!(part_1(SAMPLE_DATA), Ok(405));
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2(SAMPLE_DATA), 400);
+        assert_eq!(part_2(SAMPLE_DATA), Ok(400));
+    }
+
+    #[test]
+    fn test_not_exactly_one_reflection() {
+        let input = format!("{}\n#.\n.#\n\n#..#\n#..#\n", SAMPLE_DATA);
+        assert_eq!(
+            part_1(&input),
+            Err(ReflectionError {
+                pattern: 3,
+                num_differences: 0,
+                found: 0
+            })
+        );
+
+        let input = format!("{}\n#..#\n#..#\n", SAMPLE_DATA);
+        assert_eq!(
+            part_1(&input),
+            Err(ReflectionError {
+                pattern: 3,
+                num_differences: 0,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_all_reflections() {
+        let patterns = parse_input(SAMPLE_DATA);
+        assert_eq!(
+            patterns[0].find_all_reflections(1),
+            vec![ReflectionResult {
+                i: 3,
+                orientation: ReflectionOrientation::H,
+                smudges: vec![((0, 0), (0, 5))],
+            }]
+        );
+        assert_eq!(
+            patterns[1].find_all_reflections(1),
+            vec![ReflectionResult {
+                i: 1,
+                orientation: ReflectionOrientation::H,
+                smudges: vec![((4, 0), (4, 1))],
+            }]
+        );
+
+        // A pattern can have several mirror lines at once
+        let pattern = Pattern::new("#..#\n#..#\n");
+        assert_eq!(
+            pattern.find_all_reflections(0),
+            vec![
+                ReflectionResult {
+                    i: 1,
+                    orientation: ReflectionOrientation::H,
+                    smudges: vec![],
+                },
+                ReflectionResult {
+                    i: 2,
+                    orientation: ReflectionOrientation::V,
+                    smudges: vec![],
+                },
+            ]
+        );
+        assert_eq!(pattern.find_all_reflections(2).len(), 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let patterns = parse_input(SAMPLE_DATA);
+        assert_eq!(
+            patterns[0].render(&patterns[0].the_reflection(0).unwrap(), &RenderMode::Plain),
+            r#"     ><    
+ #.##..##. 
+ ..#.##.#. 
+ ##......# 
+ ##......# 
+ ..#.##.#. 
+ ..##..##. 
+ #.#.##.#. 
+     ><    
+"#
+        );
+        assert_eq!(
+            patterns[1].render(&patterns[1].the_reflection(1).unwrap(), &RenderMode::Plain),
+            r#"v#...*#..#v
+^#...*#..#^
+ ..##..### 
+ #####.##. 
+ #####.##. 
+ ..##..### 
+ #....#..# 
+"#
+        );
+        assert!(patterns[1]
+            .render(&patterns[1].the_reflection(1).unwrap(), &RenderMode::Ansi)
+            .starts_with("v#...\x1b[1;31m#\x1b[0m#..#v\n"));
+    }
+
+    #[test]
+    fn test_bitmasks() {
+        let pattern = Pattern::new("#.#\n..#\n");
+        assert_eq!(pattern.rows, vec![0b101, 0b100]);
+        assert_eq!(pattern.columns, vec![0b01, 0b00, 0b11]);
+    }
+
+    #[test]
+    #[should_panic(expected = "all rows should be 3 wide: ..#.")]
+    fn test_rows_of_different_width() {
+        Pattern::new("#.#\n..#.\n");
     }
 }
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::{env, fs};
+
+fn read_input_file(args: Vec<String>) -> String {
+    let default_input_filename = &String::from("input/14");
+    let input_filepath: &str = args.get(1).unwrap_or(default_input_filename);
+    fs::read_to_string(input_filepath).expect("input file should be readable")
+}
+
+fn main() {
+    let input = read_input_file(env::args().collect());
+    let result_part_1 = part_1(&input);
+    println!("{:?}", result_part_1);
+
+    let result_part_2 = part_2(&input);
+    println!("{:?}", result_part_2);
+}
+
+const ROUND_ROCK: u8 = b'O';
+const EMPTY: u8 = b'.';
+
+const SPIN_CYCLES: usize = 1_000_000_000;
+
+#[derive(Clone, Copy)]
+enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+/// The platform as a grid of `O` (round rocks), `#` (cube rocks) and `.`, stored row by row
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Platform {
+    cells: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl Platform {
+    fn new(input: &str) -> Self {
+        let lines: Vec<&str> = input.split('\n').filter(|l| !l.is_empty()).collect();
+        let width = lines.first().map_or(0, |l| l.len());
+        if let Some(line) = lines.iter().find(|l| l.len() != width) {
+            panic!("all rows should be {} wide: {}", width, line);
+        }
+        let cells: Vec<u8> = lines.iter().flat_map(|l| l.bytes()).collect();
+        if let Some(c) = cells.iter().find(|c| !b"O#.".contains(c)) {
+            panic!("invalid cell {:?}", *c as char);
+        }
+
+        Platform {
+            cells,
+            width,
+            height: lines.len(),
+        }
+    }
+
+    /// Rolls all round rocks as far as they go in the direction
+    fn tilt(&mut self, direction: Direction) {
+        // Every lane is a column or row, its cells listed from the side the rocks roll to
+        let (lanes, lane_len) = match direction {
+            Direction::North | Direction::South => (self.width, self.height),
+            Direction::West | Direction::East => (self.height, self.width),
+        };
+        for lane in 0..lanes {
+            let index = |i: usize| match direction {
+                Direction::North => i * self.width + lane,
+                Direction::South => (self.height - 1 - i) * self.width + lane,
+                Direction::West => lane * self.width + i,
+                Direction::East => lane * self.width + self.width - 1 - i,
+            };
+            // The first cell in the lane a rolling rock would stop at
+            let mut free = 0;
+            for i in 0..lane_len {
+                match self.cells[index(i)] {
+                    ROUND_ROCK => {
+                        self.cells[index(i)] = EMPTY;
+                        self.cells[index(free)] = ROUND_ROCK;
+                        free += 1;
+                    }
+                    EMPTY => {}
+                    _ => free = i + 1,
+                }
+            }
+        }
+    }
+
+    fn spin_cycle(&mut self) {
+        for direction in [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ] {
+            self.tilt(direction);
+        }
+    }
+
+    /// Each round rock adds the number of rows from it to the south edge, including its own
+    fn north_load(&self) -> usize {
+        if self.width == 0 {
+            return 0;
+        }
+        self.cells
+            .chunks(self.width)
+            .enumerate()
+            .map(|(row, cells)| {
+                cells.iter().filter(|&&c| c == ROUND_ROCK).count() * (self.height - row)
+            })
+            .sum()
+    }
+}
+
+fn part_1(input: &str) -> usize {
+    let mut platform = Platform::new(input);
+    platform.tilt(Direction::North);
+    platform.north_load()
+}
+
+/// Runs the spin cycles, once a platform repeats the rest of the cycles loop
+/// so we skip ahead to the last loop
+fn spin(mut platform: Platform, cycles: usize) -> Platform {
+    let mut seen: HashMap<Platform, usize> = HashMap::new();
+
+    for cycle in 0..cycles {
+        if let Some(first) = seen.insert(platform.clone(), cycle) {
+            let remaining = (cycles - cycle) % (cycle - first);
+            for _ in 0..remaining {
+                platform.spin_cycle();
+            }
+            return platform;
+        }
+        platform.spin_cycle();
+    }
+    platform
+}
+
+fn part_2(input: &str) -> usize {
+    spin(Platform::new(input), SPIN_CYCLES).north_load()
+}
+
+#[cfg(test)]
+mod tests_14 {
+    use super::*;
+
+    const SAMPLE_DATA: &str = r#"
+O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....
+"#;
+
+    const SAMPLE_AFTER_3_CYCLES: &str = r#"
+.....#....
+....#...O#
+.....##...
+..O#......
+.....OOO#.
+.O#...O#.#
+....O#...O
+.......OOO
+#...O###.O
+#.OOO#...O
+"#;
+
+    #[test]
+    fn test_part_1() {
+        assert_eq!(part_1(SAMPLE_DATA), 136);
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(part_2(SAMPLE_DATA), 64);
+    }
+
+    #[test]
+    fn test_empty_platform() {
+        assert_eq!(part_1(""), 0);
+        assert_eq!(part_2("\n"), 0);
+    }
+
+    #[test]
+    fn test_spin_skips_ahead() {
+        let platform = Platform::new(SAMPLE_DATA);
+        assert_eq!(
+            spin(platform.clone(), 3),
+            Platform::new(SAMPLE_AFTER_3_CYCLES)
+        );
+
+        // Skipping ahead gives the same platform as running every cycle
+        for cycles in 0..40 {
+            let mut expected = platform.clone();
+            for _ in 0..cycles {
+                expected.spin_cycle();
+            }
+            assert_eq!(spin(platform.clone(), cycles), expected);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{env, fmt, fs};
+
+fn read_input_file(args: Vec<String>) -> String {
+    let default_input_filename = &String::from("input/15");
+    let input_filepath: &str = args.get(1).unwrap_or(default_input_filename);
+    fs::read_to_string(input_filepath).expect("input file should be readable")
+}
+
+fn main() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    for flag in flags {
+        match flag.as_str() {
+            "--steps" => print_steps(&input),
+            _ => panic!("unknown flag {}", flag),
+        }
+    }
+
+    let result_part_1 = part_1(&input);
+    println!("{:?}", result_part_1);
+
+    let result_part_2 = part_2(&input);
+    println!("{:?}", result_part_2);
+}
+
+/// The Holiday ASCII String Helper algorithm, the multiplication by 17 wraps around at 256
+fn holiday_hash(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0_u8, |hash, &b| hash.wrapping_add(b).wrapping_mul(17))
+}
+
+fn steps(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split(',')
+        .map(|step| step.trim())
+        .filter(|step| !step.is_empty())
+}
+
+#[derive(Debug, PartialEq)]
+enum Operation<'a> {
+    Remove(&'a str),
+    Insert(&'a str, u8),
+}
+
+impl<'a> Operation<'a> {
+    fn parse(step: &'a str) -> Self {
+        if let Some(label) = step.strip_suffix('-') {
+            Operation::Remove(label)
+        } else if let Some((label, focal_length)) = step.split_once('=') {
+            let focal_length = focal_length
+                .parse::<u8>()
+                .ok()
+                .filter(|f| (1..=9).contains(f))
+                .unwrap_or_else(|| panic!("invalid focal length in step {}", step));
+            Operation::Insert(label, focal_length)
+        } else {
+            panic!("invalid step {}", step);
+        }
+    }
+}
+
+/// 256 boxes of lenses, every box keeps its lenses in the order they were put in
+struct LensLibrary<'a> {
+    boxes: Vec<Vec<(&'a str, u8)>>,
+}
+
+impl<'a> LensLibrary<'a> {
+    fn new() -> Self {
+        LensLibrary {
+            boxes: vec![Vec::new(); 256],
+        }
+    }
+
+    fn apply(&mut self, operation: &Operation<'a>) {
+        match *operation {
+            Operation::Remove(label) => {
+                self.boxes[holiday_hash(label.as_bytes()) as usize].retain(|(l, _)| *l != label);
+            }
+            Operation::Insert(label, focal_length) => {
+                let lenses = &mut self.boxes[holiday_hash(label.as_bytes()) as usize];
+                match lenses.iter_mut().find(|(l, _)| *l == label) {
+                    Some(lens) => lens.1 = focal_length,
+                    None => lenses.push((label, focal_length)),
+                }
+            }
+        }
+    }
+
+    fn focusing_power(&self) -> usize {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(box_number, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, (_, focal_length))| {
+                        (box_number + 1) * (slot + 1) * *focal_length as usize
+                    })
+            })
+            .sum()
+    }
+}
+
+// Shows the non-empty boxes the way the puzzle walkthrough does
+impl fmt::Debug for LensLibrary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (box_number, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {}:", box_number)?;
+            for (label, focal_length) in lenses {
+                write!(f, " [{} {}]", label, focal_length)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn print_steps(input: &str) {
+    let mut library = LensLibrary::new();
+    for step in steps(input) {
+        library.apply(&Operation::parse(step));
+        println!("After \"{}\":\n{:?}", step, library);
+    }
+}
+
+fn part_1(input: &str) -> usize {
+    steps(input)
+        .map(|step| holiday_hash(step.as_bytes()) as usize)
+        .sum()
+}
+
+fn part_2(input: &str) -> usize {
+    let mut library = LensLibrary::new();
+    for step in steps(input) {
+        library.apply(&Operation::parse(step));
+    }
+    library.focusing_power()
+}
+
+#[cfg(test)]
+mod tests_15 {
+    use super::*;
+
+    const SAMPLE_DATA: &str = r#"
+rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7
+"#;
+
+    #[test]
+    fn test_part_1() {
+        assert_eq!(part_1(SAMPLE_DATA), 1320);
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(part_2(SAMPLE_DATA), 145);
+        assert_eq!(part_2(&SAMPLE_DATA.replace('\n', "\r\n")), 145);
+    }
+
+    #[test]
+    fn test_holiday_hash() {
+        assert_eq!(holiday_hash(b"HASH"), 52);
+        assert_eq!(holiday_hash(b"rn"), 0);
+        assert_eq!(holiday_hash(b"qp"), 1);
+        assert_eq!(holiday_hash(b"pc"), 3);
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let mut library = LensLibrary::new();
+        let dumps: Vec<String> = steps(SAMPLE_DATA)
+            .map(|step| {
+                library.apply(&Operation::parse(step));
+                format!("{:?}", library)
+            })
+            .collect();
+
+        assert_eq!(dumps[0], "Box 0: [rn 1]\n");
+        assert_eq!(dumps[2], "Box 0: [rn 1]\nBox 1: [qp 3]\n");
+        assert_eq!(dumps[4], "Box 0: [rn 1] [cm 2]\n");
+        assert_eq!(
+            dumps[10],
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+    }
+}
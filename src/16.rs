@@ -0,0 +1,264 @@
+use itertools::Itertools;
+use std::{env, fs, thread};
+
+fn read_input_file(args: Vec<String>) -> String {
+    let default_input_filename = &String::from("input/16");
+    let input_filepath: &str = args.get(1).unwrap_or(default_input_filename);
+    fs::read_to_string(input_filepath).expect("input file should be readable")
+}
+
+fn main() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+    let input = read_input_file(args);
+
+    for flag in flags {
+        match flag.as_str() {
+            "--render" => print!("{}", Contraption::new(&input).energise(TOP_LEFT).render()),
+            _ => panic!("unknown flag {}", flag),
+        }
+    }
+
+    let result_part_1 = part_1(&input);
+    println!("{:?}", result_part_1);
+
+    let result_part_2 = part_2(&input);
+    println!("{:?}", result_part_2);
+}
+
+type Pos = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Every direction has its own bit in a tile's visited set
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A beam entering a tile, going in a direction
+type Beam = (Pos, Direction);
+
+const TOP_LEFT: Beam = ((0, 0), Direction::Right);
+
+struct Contraption {
+    tiles: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+/// The tiles the beams went through, per tile a bitset of the directions they went in
+struct Energised<'a> {
+    contraption: &'a Contraption,
+    visited: Vec<u8>,
+}
+
+impl Contraption {
+    fn new(input: &str) -> Self {
+        let lines = input.split('\n').filter(|l| !l.is_empty()).collect_vec();
+        let width = lines.first().map_or(0, |l| l.len());
+        if let Some(line) = lines.iter().find(|l| l.len() != width) {
+            panic!("all rows should be {} wide: {}", width, line);
+        }
+        let tiles = lines.iter().flat_map(|l| l.bytes()).collect_vec();
+        if let Some(c) = tiles.iter().find(|c| !b"./\\|-".contains(c)) {
+            panic!("invalid tile {:?}", *c as char);
+        }
+
+        Contraption {
+            tiles,
+            width,
+            height: lines.len(),
+        }
+    }
+
+    fn step(&self, (x, y): Pos, direction: Direction) -> Option<Pos> {
+        match direction {
+            Direction::Up => y.checked_sub(1).map(|y| (x, y)),
+            Direction::Down => Some((x, y + 1)).filter(|&(_, y)| y < self.height),
+            Direction::Left => x.checked_sub(1).map(|x| (x, y)),
+            Direction::Right => Some((x + 1, y)).filter(|&(x, _)| x < self.width),
+        }
+    }
+
+    fn energise(&self, start: Beam) -> Energised<'_> {
+        let mut visited = vec![0_u8; self.tiles.len()];
+        // A beam that starts outside the contraption (which can be empty) energises nothing
+        let ((x, y), _) = start;
+        let mut beams = if x < self.width && y < self.height {
+            vec![start]
+        } else {
+            Vec::new()
+        };
+
+        while let Some(((x, y), direction)) = beams.pop() {
+            let index = y * self.width + x;
+            if visited[index] & direction.bit() != 0 {
+                continue;
+            }
+            visited[index] |= direction.bit();
+
+            use Direction::*;
+            let outgoing: &[Direction] = match (self.tiles[index], direction) {
+                (b'/', Up) => &[Right],
+                (b'/', Down) => &[Left],
+                (b'/', Left) => &[Down],
+                (b'/', Right) => &[Up],
+                (b'\\', Up) => &[Left],
+                (b'\\', Down) => &[Right],
+                (b'\\', Left) => &[Up],
+                (b'\\', Right) => &[Down],
+                (b'|', Left | Right) => &[Up, Down],
+                (b'-', Up | Down) => &[Left, Right],
+                _ => &[direction],
+            };
+            for &direction in outgoing {
+                if let Some(pos) = self.step((x, y), direction) {
+                    beams.push((pos, direction));
+                }
+            }
+        }
+
+        Energised {
+            contraption: self,
+            visited,
+        }
+    }
+
+    /// All beams entering the contraption from one of its edges
+    fn entry_beams(&self) -> Vec<Beam> {
+        let (w, h) = (self.width, self.height);
+        (0..w)
+            .flat_map(|x| [((x, 0), Direction::Down), ((x, h - 1), Direction::Up)])
+            .chain((0..h).flat_map(|y| [((0, y), Direction::Right), ((w - 1, y), Direction::Left)]))
+            .collect()
+    }
+}
+
+impl Energised<'_> {
+    fn count(&self) -> usize {
+        self.visited.iter().filter(|&&v| v != 0).count()
+    }
+
+    fn render(&self) -> String {
+        if self.contraption.width == 0 {
+            return String::new();
+        }
+        self.visited
+            .chunks(self.contraption.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&v| if v != 0 { '#' } else { '.' })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+}
+
+fn part_1(input: &str) -> usize {
+    Contraption::new(input).energise(TOP_LEFT).count()
+}
+
+fn part_2(input: &str) -> usize {
+    let contraption = Contraption::new(input);
+    let entry_beams = contraption.entry_beams();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = entry_beams.len().div_ceil(threads).max(1);
+
+    // Every thread takes its own share of the entry beams
+    thread::scope(|scope| {
+        entry_beams
+            .chunks(chunk_size)
+            .map(|beams| {
+                let contraption = &contraption;
+                scope.spawn(move || {
+                    beams
+                        .iter()
+                        .map(|&beam| contraption.energise(beam).count())
+                        .max()
+                        .unwrap_or(0)
+                })
+            })
+            .collect_vec()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .max()
+            .unwrap_or(0)
+    })
+}
+
+#[cfg(test)]
+mod tests_16 {
+    use super::*;
+
+    const SAMPLE_DATA: &str = r#"
+.|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....
+"#;
+
+    #[test]
+    fn test_part_1() {
+        assert_eq!(part_1(SAMPLE_DATA), 46);
+    }
+
+    #[test]
+    fn test_part_2() {
+        assert_eq!(part_2(SAMPLE_DATA), 51);
+    }
+
+    #[test]
+    fn test_empty_contraption() {
+        assert_eq!(part_1(""), 0);
+        assert_eq!(part_2("\n"), 0);
+        assert_eq!(Contraption::new("").energise(TOP_LEFT).render(), "");
+    }
+
+    #[test]
+    fn test_render() {
+        let contraption = Contraption::new(SAMPLE_DATA);
+        assert_eq!(
+            contraption.energise(TOP_LEFT).render(),
+            r#"######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..
+"#
+        );
+    }
+
+    #[test]
+    fn test_entry_beams() {
+        let contraption = Contraption::new(SAMPLE_DATA);
+        let entry_beams = contraption.entry_beams();
+        assert_eq!(entry_beams.len(), 40);
+
+        // The best beam enters from the top, fourth column
+        let best = entry_beams
+            .iter()
+            .max_by_key(|&&beam| contraption.energise(beam).count())
+            .unwrap();
+        assert_eq!(*best, ((3, 0), Direction::Down));
+    }
+}